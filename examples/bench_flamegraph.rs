@@ -9,7 +9,7 @@ fn generate_data() -> Vec<String> {
     (0..10000).map(|n| n.to_string()).collect::<Vec<_>>()
 }
 
-fn actual_zip(out: &mut Cursor<Vec<u8>>, data: &Vec<String>) {
+fn actual_zip(out: &mut Cursor<Vec<u8>>, data: &[String]) {
     let mut writer = zip::ZipWriter::new(out);
     // for v in data.iter() {
     writer
@@ -24,7 +24,7 @@ fn actual_zip(out: &mut Cursor<Vec<u8>>, data: &Vec<String>) {
     writer.finish().unwrap();
 }
 
-fn actual_zip_stream(out: &mut Cursor<Vec<u8>>, data: &Vec<String>) {
+fn actual_zip_stream(out: &mut Cursor<Vec<u8>>, data: &[String]) {
     let mut writer = ZipWriter::new(out);
     // for v in data.iter() {
     writer
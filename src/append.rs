@@ -0,0 +1,66 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::error::{Error, Result};
+use crate::read::{ExtraFields, ZipArchive, ZipEntry};
+use crate::zip_impl::HeaderPath;
+use crate::{Header, Truncate, ZipPath, ZipWriter};
+
+impl<P: ZipPath> Header<P> {
+    fn from_entry(entry: ZipEntry) -> Self {
+        // ZIP64 extended information is written anew with the central directory
+        let mut extra_field = Vec::with_capacity(entry.extra_field.len());
+        for (id, data) in ExtraFields(&entry.extra_field).filter(|(id, _)| *id != 0x0001) {
+            extra_field.extend_from_slice(&id.to_le_bytes());
+            extra_field.extend_from_slice(&(data.len() as u16).to_le_bytes());
+            extra_field.extend_from_slice(data);
+        }
+
+        Header {
+            compression_id: entry.compression_id,
+            flags: entry.flags,
+            path: HeaderPath::Owned(entry.path),
+            modification_time: entry.modification_time,
+            modification_date: entry.modification_date,
+            compressed_size: entry.compressed_size,
+            uncompressed_size: entry.uncompressed_size,
+            crc32: entry.crc32,
+            offset: entry.offset,
+            extra_field,
+            version_made_by: Some(entry.version_made_by),
+            internal_attributes: entry.internal_attributes,
+            external_attributes: entry.external_attributes,
+            comment: entry.comment,
+            aborted: false,
+        }
+    }
+}
+
+impl<W: Read + Write + Seek + Truncate, P: ZipPath> ZipWriter<W, P> {
+    /// Opens existing archive for appending new entries: central directory
+    /// is parsed and cut off, new one with old and new entries is written on [ZipWriter::finish].
+    /// Entry comments, file attributes and archive comment of existing archive are kept.
+    /// Sink is modified only when the first entry or central directory is written,
+    /// so dropped writer leaves archive intact. New entry paths are checked with [PathPolicy::strict](crate::PathPolicy::strict)
+    pub fn open_append(sink: W) -> Result<Self> {
+        let archive = ZipArchive::new(sink).map_err(Error::InvalidArchive)?;
+        let position = archive.central_directory_offset();
+        let (sink, entries, comment) = archive.into_parts();
+
+        let mut writer = Self::new_strict(sink);
+        writer.position = position;
+        writer.committed_position = position;
+        writer.entries = entries.into_iter().map(Header::from_entry).collect();
+        writer.comment = comment;
+        writer.pending_truncate = Some(truncate_at);
+
+        Ok(writer)
+    }
+}
+
+/// Cuts off central directory of appended archive
+fn truncate_at<W: Seek + Truncate>(sink: &mut W, position: u64) -> io::Result<()> {
+    sink.truncate(position)?;
+    sink.seek(SeekFrom::Start(position))?;
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag to stop [ZipWriter](crate::ZipWriter) from another thread or task,
+/// e.g. when client disconnects. It's checked when entry starts and between writes of entry data,
+/// current entry is aborted and [Error::Cancelled](crate::Error::Cancelled) is returned.
+/// Writes are checked only for compressors, which expose inner writer with
+/// [Compressor::get_mut](crate::compressor::Compressor::get_mut), as all built-in ones do.
+/// Writer can be finished afterwards to get valid archive of complete entries
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,208 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::error::{Error, Result};
+use crate::read::invalid_data;
+use crate::zip_impl::HeaderPath;
+use crate::{Header, Truncate, ZipPath, ZipWriter};
+
+const CHECKPOINT_MAGIC: &[u8; 4] = b"ZSCP";
+const CHECKPOINT_VERSION: u16 = 2;
+/// Version without central directory only fields, which are read as empty
+const CHECKPOINT_VERSION_1: u16 = 1;
+
+/// State of [ZipWriter] after the last complete entry, which is enough to continue
+/// writing archive with [ZipWriter::resume]
+#[derive(Debug)]
+pub struct Checkpoint {
+    position: u64,
+    entries: Vec<Header<Vec<u8>>>,
+    comment: Vec<u8>,
+}
+
+impl Checkpoint {
+    /// Offset in sink, where the next entry is written
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn entries_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Serializes checkpoint in binary format, which can be read with [Checkpoint::read_from]
+    pub fn write_to(&self, mut write: impl Write) -> io::Result<()> {
+        write.write_all(CHECKPOINT_MAGIC)?;
+        write.write_u16::<LittleEndian>(CHECKPOINT_VERSION)?;
+        write.write_u64::<LittleEndian>(self.position)?;
+        write.write_u64::<LittleEndian>(self.entries.len() as u64)?;
+
+        for header in &self.entries {
+            write.write_u16::<LittleEndian>(header.compression_id)?;
+            write.write_u16::<LittleEndian>(header.flags)?;
+            write.write_u16::<LittleEndian>(header.modification_time)?;
+            write.write_u16::<LittleEndian>(header.modification_date)?;
+            write.write_u64::<LittleEndian>(header.compressed_size)?;
+            write.write_u64::<LittleEndian>(header.uncompressed_size)?;
+            write.write_u32::<LittleEndian>(header.crc32)?;
+            write.write_u64::<LittleEndian>(header.offset)?;
+            write.write_u16::<LittleEndian>(header.path_bytes().len() as u16)?;
+            write.write_all(header.path_bytes())?;
+            write.write_u16::<LittleEndian>(header.extra_field.len() as u16)?;
+            write.write_all(&header.extra_field)?;
+            // version made by is written as 0, if it's not set: version needed is never 0
+            write.write_u16::<LittleEndian>(header.version_made_by.unwrap_or(0))?;
+            write.write_u16::<LittleEndian>(header.internal_attributes)?;
+            write.write_u32::<LittleEndian>(header.external_attributes)?;
+            write.write_u16::<LittleEndian>(header.comment.len() as u16)?;
+            write.write_all(&header.comment)?;
+        }
+        write.write_u16::<LittleEndian>(self.comment.len() as u16)?;
+        write.write_all(&self.comment)?;
+
+        Ok(())
+    }
+
+    pub fn read_from(mut read: impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        read.read_exact(&mut magic)?;
+        if &magic != CHECKPOINT_MAGIC {
+            return Err(invalid_data("invalid checkpoint signature"));
+        }
+        let version = read.read_u16::<LittleEndian>()?;
+        if version != CHECKPOINT_VERSION && version != CHECKPOINT_VERSION_1 {
+            return Err(invalid_data("unsupported checkpoint version"));
+        }
+
+        let position = read.read_u64::<LittleEndian>()?;
+        let count = read.read_u64::<LittleEndian>()?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let compression_id = read.read_u16::<LittleEndian>()?;
+            let flags = read.read_u16::<LittleEndian>()?;
+            let modification_time = read.read_u16::<LittleEndian>()?;
+            let modification_date = read.read_u16::<LittleEndian>()?;
+            let compressed_size = read.read_u64::<LittleEndian>()?;
+            let uncompressed_size = read.read_u64::<LittleEndian>()?;
+            let crc32 = read.read_u32::<LittleEndian>()?;
+            let offset = read.read_u64::<LittleEndian>()?;
+            let path = read_bytes(&mut read)?;
+            let extra_field = read_bytes(&mut read)?;
+            let (version_made_by, internal_attributes, external_attributes, comment) =
+                if version == CHECKPOINT_VERSION {
+                    let version_made_by = read.read_u16::<LittleEndian>()?;
+                    let internal_attributes = read.read_u16::<LittleEndian>()?;
+                    let external_attributes = read.read_u32::<LittleEndian>()?;
+                    (
+                        Some(version_made_by).filter(|version| *version != 0),
+                        internal_attributes,
+                        external_attributes,
+                        read_bytes(&mut read)?,
+                    )
+                } else {
+                    (None, 0, 0, Vec::new())
+                };
+
+            entries.push(Header {
+                compression_id,
+                flags,
+                path: HeaderPath::Owned(path),
+                modification_time,
+                modification_date,
+                compressed_size,
+                uncompressed_size,
+                crc32,
+                offset,
+                extra_field,
+                version_made_by,
+                internal_attributes,
+                external_attributes,
+                comment,
+                aborted: false,
+            });
+        }
+        let comment = if version == CHECKPOINT_VERSION {
+            read_bytes(&mut read)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            position,
+            entries,
+            comment,
+        })
+    }
+}
+
+/// Reads bytes prefixed with 16-bit length
+fn read_bytes(mut read: impl Read) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; read.read_u16::<LittleEndian>()? as usize];
+    read.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+impl<P: ZipPath> Header<P> {
+    /// Copy of complete entry with path detached from user type
+    pub(crate) fn to_owned_path<Q: ZipPath>(&self) -> Header<Q> {
+        Header {
+            compression_id: self.compression_id,
+            flags: self.flags,
+            path: HeaderPath::Owned(self.path_bytes().to_owned()),
+            modification_time: self.modification_time,
+            modification_date: self.modification_date,
+            compressed_size: self.compressed_size,
+            uncompressed_size: self.uncompressed_size,
+            crc32: self.crc32,
+            offset: self.offset,
+            extra_field: self.extra_field.clone(),
+            version_made_by: self.version_made_by,
+            internal_attributes: self.internal_attributes,
+            external_attributes: self.external_attributes,
+            comment: self.comment.clone(),
+            aborted: self.aborted,
+        }
+    }
+}
+
+impl<W: Write, P: ZipPath> ZipWriter<W, P> {
+    /// Captures state after the last complete entry, it's O(entries),
+    /// so it's better to save it after large entries or periodically
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            position: self.committed_position,
+            entries: self.entries.iter().map(Header::to_owned_path).collect(),
+            comment: self.comment.clone(),
+        }
+    }
+}
+
+impl<W: Write + Seek + Truncate, P: ZipPath> ZipWriter<W, P> {
+    /// Continues writing archive into `sink` from `checkpoint`: everything after
+    /// checkpoint position (e.g. partially written entry) is truncated.
+    /// Policies aren't part of checkpoint and are reset to defaults
+    /// (paths are checked with [PathPolicy::strict](crate::PathPolicy::strict)), they have to be set again if needed
+    pub fn resume(mut sink: W, checkpoint: Checkpoint) -> Result<Self> {
+        if sink.seek(SeekFrom::End(0))? < checkpoint.position {
+            return Err(Error::Sink(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "sink is shorter than checkpoint position",
+            )));
+        }
+        sink.truncate(checkpoint.position)?;
+        sink.seek(SeekFrom::Start(checkpoint.position))?;
+
+        let mut writer = Self::new_strict(sink);
+        writer.position = checkpoint.position;
+        writer.committed_position = checkpoint.position;
+        writer.entries = checkpoint
+            .entries
+            .iter()
+            .map(Header::to_owned_path)
+            .collect();
+        writer.comment = checkpoint.comment;
+
+        Ok(writer)
+    }
+}
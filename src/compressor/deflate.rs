@@ -5,8 +5,15 @@ use std::io::Write;
 
 pub use flate2::Compression as DeflateConfig;
 
+/// Level of every deflated entry in reproducible mode
+pub const REPRODUCIBLE_LEVEL: u32 = 6;
+
 impl<W: WriterWrapper> CompressorConfig<W> for DeflateConfig {
     type CompressorTarget = Deflate<W>;
+
+    fn reproducible(self) -> Self {
+        DeflateConfig::new(REPRODUCIBLE_LEVEL)
+    }
 }
 
 pub struct Deflate<W: WriterWrapper> {
@@ -37,6 +44,10 @@ impl<W: WriterWrapper> Compressor for Deflate<W> {
         8
     }
 
+    fn get_mut(&mut self) -> Option<&mut Self::Inner> {
+        Some(self.inner.get_mut())
+    }
+
     fn finish(mut self) -> Result<(EntryData, Self::Inner)> {
         self.inner.try_finish()?;
         Ok((
@@ -5,7 +5,7 @@ pub mod store;
 
 pub use store::{Store, StoreConfig};
 
-use crate::Header;
+use crate::{Header, ZipPath};
 use crc32fast::Hasher;
 
 pub trait CompressorConfig<W: WriterWrapper>
@@ -18,14 +18,32 @@ where
     fn build(self, inner: <Self::CompressorTarget as Compressor>::Inner) -> Self::CompressorTarget {
         Self::CompressorTarget::new(self, inner)
     }
+
+    /// Config, which replaces this one in [ZipWriter::reproducible](crate::ZipWriter::reproducible) mode:
+    /// settings, which compressed data depends on, are fixed
+    fn reproducible(self) -> Self {
+        self
+    }
 }
 
 pub trait WriterWrapper: Write {
     type Inner;
-    type Path: AsRef<str>;
+    type Path: ZipPath;
 
     fn start_entry(&mut self, header: &mut Header<Self::Path>) -> Result<()>;
     fn end_entry(self, data: Header<Self::Path>) -> Result<Self::Inner>;
+
+    /// Called after each write with count of entry bytes received before compression,
+    /// wrappers should pass it to inner writer
+    fn progress(&mut self, _bytes_in: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called before each write of entry data, wrappers should pass it to inner writer.
+    /// Error stops writing of entry before data is consumed (e.g. on cancellation)
+    fn check_cancelled(&mut self) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Marker trait to mark owned WriteWrapper, so we call allow creating writer for it
@@ -36,6 +54,13 @@ pub trait Compressor: Write {
     type Config;
     fn new(config: Self::Config, inner: Self::Inner) -> Self;
     fn compression_id() -> u16;
+    /// Inner writer, which receives progress of entry data after each write
+    /// and checks cancellation before it. Compressors without it produce
+    /// no [Event::Progress](crate::Event::Progress) events and their entry data writes
+    /// aren't stopped by [CancellationToken](crate::CancellationToken), it's checked only when entry starts
+    fn get_mut(&mut self) -> Option<&mut Self::Inner> {
+        None
+    }
     fn finish(self) -> Result<(EntryData, Self::Inner)>;
 }
 
@@ -48,12 +73,16 @@ pub struct EntryData {
 pub struct HashWriteWrapper<W: Write> {
     inner: W,
     hasher: Hasher,
+    count: u64,
 }
 
 impl<W: Write> Write for HashWriteWrapper<W> {
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        self.hasher.update(buf);
-        self.inner.write(buf)
+        let count = self.inner.write(buf)?;
+        self.hasher.update(&buf[..count]);
+        self.count += count as u64;
+
+        Ok(count)
     }
 
     fn flush(&mut self) -> Result<()> {
@@ -62,6 +91,7 @@ impl<W: Write> Write for HashWriteWrapper<W> {
 
     fn write_all(&mut self, buf: &[u8]) -> Result<()> {
         self.hasher.update(buf);
+        self.count += buf.len() as u64;
         self.inner.write_all(buf)
     }
 }
@@ -71,9 +101,19 @@ impl<W: Write> HashWriteWrapper<W> {
         Self {
             inner,
             hasher: Hasher::new(),
+            count: 0,
         }
     }
 
+    /// Number of bytes written so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
     pub fn finish(self) -> (u32, W) {
         (self.hasher.finalize(), self.inner)
     }
@@ -44,6 +44,10 @@ impl<W: WriterWrapper> Compressor for Store<W> {
         0
     }
 
+    fn get_mut(&mut self) -> Option<&mut Self::Inner> {
+        Some(&mut self.inner)
+    }
+
     fn finish(self) -> Result<(EntryData, Self::Inner)> {
         Ok((
             EntryData {
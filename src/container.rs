@@ -0,0 +1,205 @@
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::Write;
+
+use crate::error::{Error, Result};
+use crate::observer::Event;
+use crate::zip_impl::HeaderPath;
+use crate::{dos_time, Header, Manifest, ZipPath, ZipWriter};
+
+const MIMETYPE_PATH: &[u8] = b"mimetype";
+const CONTENT_TYPES_PATH: &[u8] = b"[Content_Types].xml";
+const META_INF_PATH: &[u8] = b"META-INF/";
+const MANIFEST_PATH: &[u8] = b"META-INF/MANIFEST.MF";
+
+/// Layout rules of document formats based on zip, see [ZipWriter::container]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContainerProfile {
+    /// EPUB: the first entry is `mimetype` with `application/epub+zip`
+    Epub,
+    /// OpenDocument: the first entry is `mimetype` with given media type,
+    /// e.g. `application/vnd.oasis.opendocument.text`
+    OpenDocument(String),
+    /// Office Open XML: the first entry has to be `[Content_Types].xml`
+    Ooxml,
+    /// Java archive: the first entries are `META-INF/` marked with JAR extra field (0xCAFE)
+    /// and `META-INF/MANIFEST.MF`. Missing parent directory entries are written
+    /// before entries and duplicate paths are rejected, like Java tools expect
+    Jar(Manifest),
+}
+
+impl ContainerProfile {
+    /// Media type is written as is and compared by readers byte by byte, so it can't contain
+    /// anything except printable ASCII
+    fn validate(&self) -> Result<()> {
+        match self {
+            ContainerProfile::Jar(manifest) => manifest.validate().map_err(Error::ContainerLayout),
+            ContainerProfile::OpenDocument(media_type)
+                if media_type.is_empty() || !media_type.bytes().all(|b| b.is_ascii_graphic()) =>
+            {
+                Err(Error::ContainerLayout(format!(
+                    "invalid media type {:?}",
+                    media_type
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Content of `mimetype` entry, which is written by profile
+    fn mimetype(&self) -> Option<&[u8]> {
+        match self {
+            ContainerProfile::Epub => Some(b"application/epub+zip"),
+            ContainerProfile::OpenDocument(media_type) => Some(media_type.as_bytes()),
+            ContainerProfile::Ooxml | ContainerProfile::Jar(_) => None,
+        }
+    }
+}
+
+impl<W: Write, P: ZipPath> ZipWriter<W, P> {
+    /// Checks that entry can be written at current position and writes leading entries of profile
+    pub(crate) fn apply_container(&mut self, header: &Header<P>) -> Result<()> {
+        let profile = match &self.container {
+            Some(profile) => profile,
+            None => return Ok(()),
+        };
+
+        if profile.mimetype().is_some() && header.path_bytes() == MIMETYPE_PATH {
+            return Err(Error::ContainerLayout(
+                "mimetype entry is written by container profile".to_owned(),
+            ));
+        }
+        if *profile == ContainerProfile::Ooxml
+            && self.entries.is_empty()
+            && header.path_bytes() != CONTENT_TYPES_PATH
+        {
+            return Err(Error::ContainerLayout(format!(
+                "the first entry must be [Content_Types].xml, not {:?}",
+                header.path_lossy()
+            )));
+        }
+
+        self.start_container()
+    }
+
+    /// Path, which parent directory entries are made of, it's taken before path encoding
+    pub(crate) fn parent_dirs_path(&self, header: &Header<P>) -> Option<Vec<u8>> {
+        match self.container {
+            Some(ContainerProfile::Jar(_)) => Some(header.path_bytes().to_owned()),
+            _ => None,
+        }
+    }
+
+    /// Writes leading entries of profile, if archive is empty
+    pub(crate) fn start_container(&mut self) -> Result<()> {
+        if !self.entries.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(profile) = &self.container {
+            profile.validate()?;
+        }
+        match &self.container {
+            Some(ContainerProfile::Jar(manifest)) => {
+                let manifest = manifest.to_bytes();
+                // JAR magic, which marks archive as executable on Solaris
+                self.write_stored_entry(META_INF_PATH, &[], &[0xFE, 0xCA, 0, 0])?;
+                self.write_stored_entry(MANIFEST_PATH, &manifest, &[])
+            }
+            Some(profile) => match profile.mimetype() {
+                Some(mimetype) => {
+                    let mimetype = mimetype.to_owned();
+                    // readers detect format by magic bytes at fixed offsets, so there is no extra field
+                    self.write_stored_entry(MIMETYPE_PATH, &mimetype, &[])
+                }
+                None => Ok(()),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Writes entries of directories in `path`, which aren't in archive yet.
+    /// It's called after entry passes all checks, so rejected entry doesn't leave directories
+    pub(crate) fn write_parent_dirs(&mut self, path: &[u8]) -> Result<()> {
+        let dir_ends = path
+            .iter()
+            .enumerate()
+            .filter(|(i, b)| **b == b'/' && i + 1 < path.len())
+            .map(|(i, _)| i + 1);
+        for end in dir_ends {
+            let dir = &path[..end];
+            let encoded = self.path_encoding.encode(dir);
+            let exists = match &self.paths {
+                Some(paths) => paths.contains(&*encoded),
+                None => self.entries.iter().any(|e| e.path_bytes() == &*encoded),
+            };
+            if !exists {
+                self.write_stored_entry(dir, &[], &[])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes complete stored entry with sizes in local header, so it has neither
+    /// ZIP64 extra field nor data descriptor. Path is encoded like paths of other entries
+    fn write_stored_entry(&mut self, path: &[u8], data: &[u8], extra_field: &[u8]) -> Result<()> {
+        let (modification_time, modification_date) = self
+            .reproducible
+            .unwrap_or((dos_time::DEFAULT_TIME, dos_time::DEFAULT_DATE));
+
+        let mut header = Header {
+            compression_id: 0,
+            flags: 0, // sizes are known, so there is no data descriptor
+            path: HeaderPath::Owned(path.to_owned()),
+            modification_time,
+            modification_date,
+            compressed_size: data.len() as u64,
+            uncompressed_size: data.len() as u64,
+            crc32: crc32fast::hash(data),
+            offset: self.position,
+            extra_field: extra_field.to_owned(),
+            version_made_by: None,
+            internal_attributes: 0,
+            external_attributes: 0,
+            comment: Vec::new(),
+            aborted: false,
+        };
+        header.apply_path_encoding(self.path_encoding)?;
+        let path = header.path_bytes();
+        let extra_field = &header.extra_field;
+
+        self.prepare_sink()?;
+        self.notify(Event::EntryStarted { path });
+        self.write.write_u32::<LittleEndian>(0x04034b50)?; // magic number
+        self.write
+            .write_u16::<LittleEndian>(header.version_needed())?; // version
+        self.write.write_u16::<LittleEndian>(header.flags)?; // general purpose flag
+        self.write
+            .write_u16::<LittleEndian>(header.compression_id)?; // compression method
+        self.write
+            .write_u16::<LittleEndian>(header.modification_time)?; // modification_time
+        self.write
+            .write_u16::<LittleEndian>(header.modification_date)?; // modification_date
+        self.write.write_u32::<LittleEndian>(header.crc32)?; // crc-32
+        self.write
+            .write_u32::<LittleEndian>(header.compressed_size as u32)?; // compressed size
+        self.write
+            .write_u32::<LittleEndian>(header.uncompressed_size as u32)?; // uncompressed size
+        self.write.write_u16::<LittleEndian>(path.len() as u16)?; // file name length
+        self.write
+            .write_u16::<LittleEndian>(extra_field.len() as u16)?; // extra field length
+        self.write.write_all(path)?; // path
+        self.write.write_all(extra_field)?;
+        self.write.write_all(data)?;
+
+        self.position += 30 + path.len() as u64 + extra_field.len() as u64 + data.len() as u64;
+        self.committed_position = self.position;
+        if let Some(paths) = &mut self.paths {
+            paths.insert(path.to_owned());
+        }
+        self.notify_entry_end(&header);
+        self.entries.push(header);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Component, Path};
+
+use walkdir::WalkDir;
+
+use crate::error::{Error, Result};
+use crate::{ZipPath, ZipWriter};
+
+impl<W: Write, P: ZipPath> ZipWriter<W, P> {
+    /// Appends content of `dir` recursively in file name order, entry paths are relative to `dir`.
+    /// Directories are written as empty entries with trailing `/`, symlinks are skipped.
+    /// Cancellation is checked before each entry
+    pub fn append_dir(&mut self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        let walker = WalkDir::new(dir)
+            .min_depth(1)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()));
+
+        for entry in walker {
+            self.check_cancelled()?;
+
+            let entry = entry.map_err(|e| Error::Source {
+                path: e.path().unwrap_or(dir).to_string_lossy().into_owned(),
+                source: io::Error::from(e),
+            })?;
+            let source_error = |source| Error::Source {
+                path: entry.path().to_string_lossy().into_owned(),
+                source,
+            };
+
+            let mut path = relative_path(entry.path().strip_prefix(dir).unwrap());
+            if entry.file_type().is_dir() {
+                path.push(b'/');
+                let builder = self.start_file_owned(path);
+                let builder = match entry.metadata().ok().and_then(|m| m.modified().ok()) {
+                    Some(modified) => builder.modification(modified),
+                    None => builder,
+                };
+                builder.write_all(&[])?;
+            } else if entry.file_type().is_file() {
+                let file = File::open(entry.path()).map_err(source_error)?;
+                self.start_file_owned(path)
+                    .modification_from_file(&file)
+                    .write_data(file)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Components joined with `/`
+fn relative_path(path: &Path) -> Vec<u8> {
+    let mut result = Vec::new();
+    for component in path.components() {
+        if let Component::Normal(name) = component {
+            if !result.is_empty() {
+                result.push(b'/');
+            }
+            #[cfg(unix)]
+            result.extend_from_slice(name.as_path_bytes());
+            #[cfg(not(unix))]
+            result.extend_from_slice(name.to_string_lossy().as_bytes());
+        }
+    }
+
+    result
+}
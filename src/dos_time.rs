@@ -0,0 +1,213 @@
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike};
+#[cfg(feature = "time")]
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+/// 00:00:00
+pub(crate) const DEFAULT_TIME: u16 = 0;
+/// 1980-01-01, the earliest date MS-DOS format can hold
+pub(crate) const DEFAULT_DATE: u16 = 1 << 5 | 1;
+/// 23:59:58
+const MAX_TIME: u16 = 23 << 11 | 59 << 5 | 29;
+/// 2107-12-31, the latest date MS-DOS format can hold
+const MAX_DATE: u16 = 127 << 9 | 12 << 5 | 31;
+
+/// Time zone of MS-DOS date time fields. They have no zone and most unzip tools
+/// treat them as local time of extracting machine, so [TimeZone::Utc] timestamps
+/// are shifted by its offset. Extended timestamp extra field is always in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum TimeZone {
+    #[default]
+    Utc,
+    /// Offset of the writing machine at the moment of timestamp, it's determined with `chrono`
+    /// or `time` feature. Falls back to UTC, when it can't be determined, e.g. without them
+    /// or with `time` only on Unix in multithreaded process, see `time::UtcOffset::local_offset_at`
+    Local,
+    /// Fixed offset east of UTC in seconds
+    Offset(i32),
+}
+
+impl TimeZone {
+    /// Offset in seconds at given seconds since Unix epoch
+    fn offset_at(self, seconds: i64) -> i64 {
+        match self {
+            TimeZone::Utc => 0,
+            TimeZone::Local => local_offset_at(seconds),
+            TimeZone::Offset(offset) => offset as i64,
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn local_offset_at(seconds: i64) -> i64 {
+    use chrono::{Offset, TimeZone as _};
+
+    chrono::Local
+        .timestamp_opt(seconds, 0)
+        .earliest()
+        .map_or(0, |date_time| {
+            date_time.offset().fix().local_minus_utc() as i64
+        })
+}
+
+#[cfg(all(feature = "time", not(feature = "chrono")))]
+fn local_offset_at(seconds: i64) -> i64 {
+    OffsetDateTime::from_unix_timestamp(seconds)
+        .ok()
+        .and_then(|date_time| time::UtcOffset::local_offset_at(date_time).ok())
+        .map_or(0, |offset| offset.whole_seconds() as i64)
+}
+
+#[cfg(not(any(feature = "time", feature = "chrono")))]
+fn local_offset_at(_seconds: i64) -> i64 {
+    0
+}
+
+/// Packs date time into MS-DOS time and date fields, seconds are rounded down to even.
+/// Years before 1980 are clamped to 1980-01-01 00:00:00 and after 2107 to 2107-12-31 23:59:58
+fn encode(year: i64, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> (u16, u16) {
+    if year < 1980 {
+        return (DEFAULT_TIME, DEFAULT_DATE);
+    }
+    if year > 2107 {
+        return (MAX_TIME, MAX_DATE);
+    }
+
+    let time = (second / 2) as u16 | // 0-4 bits
+        (minute as u16) << 5 | // 5-10 bits
+        (hour as u16) << 11; // 11-15 bits
+    let date = day as u16 | // 0-4 bits
+        (month as u16) << 5 | // 5-8 bits
+        ((year - 1980) as u16) << 9; // 9-15 bits
+
+    (time, date)
+}
+
+/// Converts seconds since Unix epoch (UTC) into MS-DOS time and date fields
+pub(crate) fn from_unix(seconds: i64) -> (u16, u16) {
+    let days = seconds.div_euclid(86400);
+    let seconds_of_day = seconds.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+
+    encode(
+        year,
+        month,
+        day,
+        (seconds_of_day / 3600) as u8,
+        (seconds_of_day / 60 % 60) as u8,
+        (seconds_of_day % 60) as u8,
+    )
+}
+
+/// Converts seconds since Unix epoch into MS-DOS time and date fields in `time_zone`
+pub(crate) fn from_unix_in(seconds: i64, time_zone: TimeZone) -> (u16, u16) {
+    from_unix(seconds.saturating_add(time_zone.offset_at(seconds)))
+}
+
+/// Seconds since Unix epoch, saturated to [i64] range
+pub(crate) fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => i64::try_from(duration.as_secs()).unwrap_or(i64::MAX),
+        Err(e) => -i64::try_from(e.duration().as_secs()).unwrap_or(i64::MAX),
+    }
+}
+
+/// Year, month and day of days since Unix epoch, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097); // [0, 146096]
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365; // [0, 399]
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100); // [0, 365]
+    let month_from_march = (5 * day_of_year + 2) / 153; // [0, 11]
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u8;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u8;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+/// Converts date time into MS-DOS time and date fields in its own offset
+#[cfg(feature = "time")]
+pub(crate) fn from_date_time(date_time: OffsetDateTime) -> (u16, u16) {
+    encode(
+        date_time.year() as i64,
+        date_time.month() as u8,
+        date_time.day(),
+        date_time.hour(),
+        date_time.minute(),
+        date_time.second(),
+    )
+}
+
+/// Converts date time into MS-DOS time and date fields in its own offset
+#[cfg(feature = "chrono")]
+pub(crate) fn from_chrono<Tz: chrono::TimeZone>(date_time: &DateTime<Tz>) -> (u16, u16) {
+    encode(
+        date_time.year() as i64,
+        date_time.month() as u8,
+        date_time.day() as u8,
+        date_time.hour() as u8,
+        date_time.minute() as u8,
+        date_time.second() as u8,
+    )
+}
+
+/// Same as [to_date_time], but with chrono and without time zone, which fields don't have
+#[cfg(feature = "chrono")]
+pub(crate) fn to_chrono(time: u16, date: u16) -> Option<NaiveDateTime> {
+    let date = NaiveDate::from_ymd_opt(
+        1980 + (date >> 9) as i32,     // 9-15 bits
+        ((date >> 5) & 0b1111) as u32, // 5-8 bits
+        (date & 0b1_1111) as u32,      // 0-4 bits
+    )?;
+    let date_time = date.and_hms_opt(
+        (time >> 11) as u32,              // 11-15 bits
+        ((time >> 5) & 0b11_1111) as u32, // 5-10 bits
+        ((time & 0b1_1111) * 2) as u32,   // 0-4 bits
+    )?;
+
+    Some(date_time)
+}
+
+/// Converts MS-DOS time and date fields back to date time,
+/// returns [None] if fields contain invalid date
+#[cfg(feature = "time")]
+pub(crate) fn to_date_time(time: u16, date: u16) -> Option<OffsetDateTime> {
+    let date = Date::from_calendar_date(
+        1980 + (date >> 9) as i32,                           // 9-15 bits
+        Month::try_from(((date >> 5) & 0b1111) as u8).ok()?, // 5-8 bits
+        (date & 0b1_1111) as u8,                             // 0-4 bits
+    )
+    .ok()?;
+    let time = Time::from_hms(
+        (time >> 11) as u8,              // 11-15 bits
+        ((time >> 5) & 0b11_1111) as u8, // 5-10 bits
+        ((time & 0b1_1111) * 2) as u8,   // 0-4 bits
+    )
+    .ok()?;
+
+    Some(PrimitiveDateTime::new(date, time).assume_utc())
+}
+
+/// Formats MS-DOS time and date fields as `YYYY-MM-DDTHH:MM:SS` without validation
+pub(crate) fn format(time: u16, date: u16) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        1980 + (date >> 9),
+        (date >> 5) & 0b1111,
+        date & 0b1_1111,
+        time >> 11,
+        (time >> 5) & 0b11_1111,
+        (time & 0b1_1111) * 2
+    )
+}
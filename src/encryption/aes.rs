@@ -0,0 +1,273 @@
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes192, Aes256};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::io::{Error, Result, Write};
+
+use crate::compressor::{WriterWrapper, WriterWrapperOwned};
+use crate::encryption::EncryptionConfig;
+use crate::Header;
+
+const PBKDF2_ITERATIONS: u32 = 1000;
+const PASSWORD_VERIFIER_LENGTH: u64 = 2;
+const AUTHENTICATION_CODE_LENGTH: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AesStrength {
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+impl AesStrength {
+    fn key_length(self) -> usize {
+        match self {
+            AesStrength::Aes128 => 16,
+            AesStrength::Aes192 => 24,
+            AesStrength::Aes256 => 32,
+        }
+    }
+
+    fn salt_length(self) -> usize {
+        self.key_length() / 2
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            AesStrength::Aes128 => 1,
+            AesStrength::Aes192 => 2,
+            AesStrength::Aes256 => 3,
+        }
+    }
+}
+
+/// AE-2 doesn't store crc of the data, so it doesn't leak any information about small files,
+/// integrity is verified with authentication code only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AesVendorVersion {
+    Ae1,
+    Ae2,
+}
+
+/// WinZip AES encryption (compression method 99)
+///
+/// Key is derived from password with PBKDF2-HMAC-SHA1 and random salt,
+/// data is encrypted with AES in CTR mode and authenticated with HMAC-SHA1
+#[derive(Clone)]
+pub struct AesConfig {
+    password: Vec<u8>,
+    strength: AesStrength,
+    vendor_version: AesVendorVersion,
+}
+
+impl AesConfig {
+    /// AES-256 with AE-2 format
+    pub fn new(password: impl AsRef<[u8]>) -> Self {
+        Self {
+            password: password.as_ref().to_vec(),
+            strength: AesStrength::Aes256,
+            vendor_version: AesVendorVersion::Ae2,
+        }
+    }
+
+    pub fn strength(mut self, strength: AesStrength) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    pub fn vendor_version(mut self, vendor_version: AesVendorVersion) -> Self {
+        self.vendor_version = vendor_version;
+        self
+    }
+}
+
+impl<W: WriterWrapper> EncryptionConfig<W> for AesConfig {
+    type EncryptorTarget = Aes<W>;
+
+    fn build(self, inner: W) -> Self::EncryptorTarget {
+        Aes {
+            inner,
+            config: self,
+            state: None,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+enum Cipher {
+    Aes128(Box<Aes128>),
+    Aes192(Box<Aes192>),
+    Aes256(Box<Aes256>),
+}
+
+impl Cipher {
+    fn new(strength: AesStrength, key: &[u8]) -> Self {
+        match strength {
+            AesStrength::Aes128 => {
+                Cipher::Aes128(Box::new(Aes128::new(GenericArray::from_slice(key))))
+            }
+            AesStrength::Aes192 => {
+                Cipher::Aes192(Box::new(Aes192::new(GenericArray::from_slice(key))))
+            }
+            AesStrength::Aes256 => {
+                Cipher::Aes256(Box::new(Aes256::new(GenericArray::from_slice(key))))
+            }
+        }
+    }
+
+    fn encrypt_block(&self, block: &mut [u8; 16]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            Cipher::Aes128(cipher) => cipher.encrypt_block(block),
+            Cipher::Aes192(cipher) => cipher.encrypt_block(block),
+            Cipher::Aes256(cipher) => cipher.encrypt_block(block),
+        }
+    }
+}
+
+struct AesState {
+    cipher: Cipher,
+    hmac: Hmac<Sha1>,
+    /// WinZip uses little endian counter starting from 1
+    counter: u128,
+    keystream: [u8; 16],
+    keystream_position: usize,
+}
+
+impl AesState {
+    fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            if self.keystream_position == self.keystream.len() {
+                self.counter += 1;
+                self.keystream = self.counter.to_le_bytes();
+                self.cipher.encrypt_block(&mut self.keystream);
+                self.keystream_position = 0;
+            }
+
+            *byte ^= self.keystream[self.keystream_position];
+            self.keystream_position += 1;
+        }
+        self.hmac.update(data);
+    }
+}
+
+pub struct Aes<W: WriterWrapper> {
+    inner: W,
+    config: AesConfig,
+    state: Option<AesState>,
+    buffer: Vec<u8>,
+}
+
+impl<W: WriterWrapper> Aes<W> {
+    fn state(&mut self) -> Result<&mut AesState> {
+        self.state
+            .as_mut()
+            .ok_or_else(|| Error::other("entry is not started"))
+    }
+}
+
+impl<W: WriterWrapper> Write for Aes<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    /// Keystream is consumed on encryption, so data is always written as a whole
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        buffer.extend_from_slice(buf);
+
+        self.state()?.encrypt(&mut buffer);
+        let result = self.inner.write_all(&buffer);
+
+        self.buffer = buffer;
+        result
+    }
+}
+
+impl<W: WriterWrapper> WriterWrapper for Aes<W> {
+    type Inner = W::Inner;
+    type Path = W::Path;
+
+    fn start_entry(&mut self, header: &mut Header<Self::Path>) -> Result<()> {
+        let strength = self.config.strength;
+        let key_length = strength.key_length();
+
+        let mut salt = vec![0; strength.salt_length()];
+        getrandom::getrandom(&mut salt).map_err(Error::from)?;
+
+        let mut derived = vec![0; key_length * 2 + PASSWORD_VERIFIER_LENGTH as usize];
+        pbkdf2::pbkdf2_hmac::<Sha1>(
+            &self.config.password,
+            &salt,
+            PBKDF2_ITERATIONS,
+            &mut derived,
+        );
+        let (encryption_key, rest) = derived.split_at(key_length);
+        let (authentication_key, password_verifier) = rest.split_at(key_length);
+
+        let mut extra = Vec::with_capacity(7);
+        extra.extend_from_slice(
+            &match self.config.vendor_version {
+                AesVendorVersion::Ae1 => 1u16,
+                AesVendorVersion::Ae2 => 2u16,
+            }
+            .to_le_bytes(),
+        );
+        extra.extend_from_slice(b"AE");
+        extra.push(strength.id());
+        extra.extend_from_slice(&header.compression_id.to_le_bytes()); // actual compression method
+        header.add_extra_field(0x9901, &extra);
+        header.compression_id = 99;
+        header.flags |= 0x0001;
+
+        self.inner.start_entry(header)?;
+        self.inner.write_all(&salt)?;
+        self.inner.write_all(password_verifier)?;
+
+        self.state = Some(AesState {
+            cipher: Cipher::new(strength, encryption_key),
+            hmac: <Hmac<Sha1> as Mac>::new_from_slice(authentication_key).map_err(Error::other)?,
+            counter: 0,
+            keystream: [0; 16],
+            keystream_position: 16,
+        });
+
+        Ok(())
+    }
+
+    fn end_entry(mut self, mut header: Header<Self::Path>) -> Result<Self::Inner> {
+        let state = self
+            .state
+            .take()
+            .ok_or_else(|| Error::other("entry is not started"))?;
+        let authentication_code = state.hmac.finalize().into_bytes();
+        self.inner
+            .write_all(&authentication_code[..AUTHENTICATION_CODE_LENGTH])?;
+
+        header.compressed_size += self.config.strength.salt_length() as u64
+            + PASSWORD_VERIFIER_LENGTH
+            + AUTHENTICATION_CODE_LENGTH as u64;
+        if self.config.vendor_version == AesVendorVersion::Ae2 {
+            header.crc32 = 0;
+        }
+
+        self.inner.end_entry(header)
+    }
+
+    fn progress(&mut self, bytes_in: u64) -> Result<()> {
+        self.inner.progress(bytes_in)
+    }
+
+    fn check_cancelled(&mut self) -> Result<()> {
+        self.inner.check_cancelled()
+    }
+}
+
+impl<W: WriterWrapper + WriterWrapperOwned> WriterWrapperOwned for Aes<W> {}
@@ -0,0 +1,21 @@
+use crate::compressor::WriterWrapper;
+
+#[cfg(feature = "aes-crypto")]
+pub mod aes;
+#[cfg(feature = "zip-crypto")]
+pub mod zip_crypto;
+
+#[cfg(feature = "aes-crypto")]
+pub use aes::{Aes, AesConfig, AesStrength, AesVendorVersion};
+#[cfg(feature = "zip-crypto")]
+pub use zip_crypto::{ZipCrypto, ZipCryptoConfig};
+
+/// Encryption is applied to compressor output, right before it's written into archive
+pub trait EncryptionConfig<W: WriterWrapper>
+where
+    Self: Sized,
+{
+    type EncryptorTarget: WriterWrapper<Inner = W::Inner, Path = W::Path>;
+
+    fn build(self, inner: W) -> Self::EncryptorTarget;
+}
@@ -0,0 +1,144 @@
+use std::io::{Error, Result, Write};
+
+use crate::compressor::{WriterWrapper, WriterWrapperOwned};
+use crate::encryption::EncryptionConfig;
+use crate::Header;
+
+const ENCRYPTION_HEADER_LENGTH: u64 = 12;
+
+/// Traditional PKWARE encryption (ZipCrypto).
+///
+/// **It's weak** and can be broken with known plaintext attack in minutes,
+/// use it only for compatibility with tools, which don't support [AesConfig](crate::encryption::AesConfig).
+///
+/// Crc is unknown when encryption header is written, so high byte of
+/// modification time is used as a password check byte, like Info-ZIP does for entries with data descriptor.
+#[derive(Clone)]
+pub struct ZipCryptoConfig {
+    password: Vec<u8>,
+}
+
+impl ZipCryptoConfig {
+    pub fn new(password: impl AsRef<[u8]>) -> Self {
+        Self {
+            password: password.as_ref().to_vec(),
+        }
+    }
+}
+
+impl<W: WriterWrapper> EncryptionConfig<W> for ZipCryptoConfig {
+    type EncryptorTarget = ZipCrypto<W>;
+
+    fn build(self, inner: W) -> Self::EncryptorTarget {
+        let mut keys = Keys::new();
+        for &byte in self.password.iter() {
+            keys.update(byte);
+        }
+
+        ZipCrypto {
+            inner,
+            keys,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Keys([u32; 3]);
+
+impl Keys {
+    fn new() -> Self {
+        Keys([0x12345678, 0x23456789, 0x34567890])
+    }
+
+    fn update(&mut self, byte: u8) {
+        self.0[0] = crc32_update(self.0[0], byte);
+        self.0[1] = self.0[1]
+            .wrapping_add(self.0[0] & 0xFF)
+            .wrapping_mul(134775813)
+            .wrapping_add(1);
+        self.0[2] = crc32_update(self.0[2], (self.0[1] >> 24) as u8);
+    }
+
+    fn stream_byte(&self) -> u8 {
+        let temp = (self.0[2] | 2) as u16;
+        (temp.wrapping_mul(temp ^ 1) >> 8) as u8
+    }
+
+    fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            let plain = *byte;
+            *byte ^= self.stream_byte();
+            self.update(plain);
+        }
+    }
+}
+
+/// One step of CRC-32 without initial and final inversion, which is used by key schedule
+fn crc32_update(crc: u32, byte: u8) -> u32 {
+    let mut hasher = crc32fast::Hasher::new_with_initial(!crc);
+    hasher.update(&[byte]);
+    !hasher.finalize()
+}
+
+pub struct ZipCrypto<W: WriterWrapper> {
+    inner: W,
+    keys: Keys,
+    buffer: Vec<u8>,
+}
+
+impl<W: WriterWrapper> Write for ZipCrypto<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    /// Keys are updated on encryption, so data is always written as a whole
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        buffer.extend_from_slice(buf);
+
+        self.keys.encrypt(&mut buffer);
+        let result = self.inner.write_all(&buffer);
+
+        self.buffer = buffer;
+        result
+    }
+}
+
+impl<W: WriterWrapper> WriterWrapper for ZipCrypto<W> {
+    type Inner = W::Inner;
+    type Path = W::Path;
+
+    fn start_entry(&mut self, header: &mut Header<Self::Path>) -> Result<()> {
+        header.flags |= 0x0001;
+        self.inner.start_entry(header)?;
+
+        let mut encryption_header = [0; ENCRYPTION_HEADER_LENGTH as usize];
+        getrandom::getrandom(&mut encryption_header[..11]).map_err(Error::from)?;
+        encryption_header[11] = (header.modification_time >> 8) as u8;
+
+        self.keys.encrypt(&mut encryption_header);
+        self.inner.write_all(&encryption_header)
+    }
+
+    fn end_entry(self, mut header: Header<Self::Path>) -> Result<Self::Inner> {
+        header.compressed_size += ENCRYPTION_HEADER_LENGTH;
+        self.inner.end_entry(header)
+    }
+
+    fn progress(&mut self, bytes_in: u64) -> Result<()> {
+        self.inner.progress(bytes_in)
+    }
+
+    fn check_cancelled(&mut self) -> Result<()> {
+        self.inner.check_cancelled()
+    }
+}
+
+impl<W: WriterWrapper + WriterWrapperOwned> WriterWrapperOwned for ZipCrypto<W> {}
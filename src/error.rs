@@ -0,0 +1,135 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::path::PathError;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Entry can't be represented within limits of zip format
+    FormatLimit(FormatLimit),
+    /// Entry path was rejected by [PathPolicy](crate::PathPolicy)
+    InvalidPath { path: String, reason: PathError },
+    /// Entry with the same path was already written and [DuplicatePolicy](crate::DuplicatePolicy) forbids duplicates
+    DuplicatePath(String),
+    /// Entry breaks layout of [ContainerProfile](crate::ContainerProfile)
+    ContainerLayout(String),
+    /// Writing was stopped with [CancellationToken](crate::CancellationToken)
+    Cancelled,
+    /// Reading entry data from user provided source failed
+    Source { path: String, source: io::Error },
+    /// Writing archive into underlying writer failed
+    Sink(io::Error),
+    /// Existing archive, opened with [ZipWriter::open_append](crate::ZipWriter::open_append), can't be read
+    InvalidArchive(io::Error),
+    /// Compression or encryption of entry data failed
+    Compressor(io::Error),
+}
+
+/// Limits of zip format, which an entry can exceed. There is no limit of entry count
+/// or archive size, because ZIP64 end of central directory is always written
+/// and ZIP64 can't be turned off
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatLimit {
+    /// Path is longer than 65535 bytes
+    PathTooLong(usize),
+    /// Extra field is longer than 65535 bytes
+    ExtraFieldTooLong(usize),
+}
+
+impl Error {
+    /// Wraps sink error into [io::Error], so it can go through [io::Write] implementations
+    /// and can be told apart from compressor errors afterwards
+    pub(crate) fn sink(e: io::Error) -> io::Error {
+        Error::Sink(e).into()
+    }
+
+    /// Errors returned from compressor chain, which aren't tagged as sink errors, are compressor ones
+    pub(crate) fn compressor(e: io::Error) -> Self {
+        Self::unwrap_io(e).unwrap_or_else(Error::Compressor)
+    }
+
+    fn unwrap_io(e: io::Error) -> std::result::Result<Self, io::Error> {
+        if e.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            Ok(*e.into_inner().unwrap().downcast::<Error>().unwrap())
+        } else {
+            Err(e)
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::FormatLimit(limit) => write!(f, "zip format limit exceeded: {}", limit),
+            Error::InvalidPath { path, reason } => {
+                write!(f, "invalid entry path {:?}: {}", path, reason)
+            }
+            Error::DuplicatePath(path) => write!(f, "entry {:?} already exists", path),
+            Error::ContainerLayout(reason) => write!(f, "invalid container layout: {}", reason),
+            Error::Cancelled => write!(f, "archive writing was cancelled"),
+            Error::Source { path, source } => {
+                write!(f, "failed to read data of entry {:?}: {}", path, source)
+            }
+            Error::Sink(e) => write!(f, "failed to write archive: {}", e),
+            Error::InvalidArchive(e) => write!(f, "failed to read existing archive: {}", e),
+            Error::Compressor(e) => write!(f, "failed to compress entry data: {}", e),
+        }
+    }
+}
+
+impl Display for FormatLimit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatLimit::PathTooLong(length) => {
+                write!(f, "path is {} bytes long, maximum is 65535", length)
+            }
+            FormatLimit::ExtraFieldTooLong(length) => {
+                write!(f, "extra field is {} bytes long, maximum is 65535", length)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::FormatLimit(_)
+            | Error::InvalidPath { .. }
+            | Error::DuplicatePath(_)
+            | Error::ContainerLayout(_)
+            | Error::Cancelled => None,
+            Error::Source { source, .. } => Some(source),
+            Error::Sink(e) | Error::InvalidArchive(e) | Error::Compressor(e) => Some(e),
+        }
+    }
+}
+
+impl From<FormatLimit> for Error {
+    fn from(limit: FormatLimit) -> Self {
+        Error::FormatLimit(limit)
+    }
+}
+
+/// Errors of writes directly into underlying writer are sink errors
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::unwrap_io(e).unwrap_or_else(Error::Sink)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        let kind = match &e {
+            Error::FormatLimit(_) | Error::InvalidPath { .. } | Error::ContainerLayout(_) => {
+                io::ErrorKind::InvalidInput
+            }
+            Error::DuplicatePath(_) => io::ErrorKind::AlreadyExists,
+            Error::Cancelled => io::ErrorKind::Other,
+            Error::Source { source, .. } => source.kind(),
+            Error::Sink(e) | Error::InvalidArchive(e) | Error::Compressor(e) => e.kind(),
+        };
+        io::Error::new(kind, e)
+    }
+}
@@ -0,0 +1,144 @@
+/// Maximum length of manifest line in bytes without line break
+const MAX_LINE_LENGTH: usize = 72;
+/// Maximum length of attribute name in bytes, so `name: ` fits the first line
+const MAX_NAME_LENGTH: usize = 70;
+
+/// Content of `META-INF/MANIFEST.MF`, which is written by [ContainerProfile::Jar](crate::ContainerProfile::Jar).
+/// Attributes keep insertion order, `Manifest-Version: 1.0` is always the first one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    main_attributes: Vec<(String, String)>,
+    /// Per-entry sections: entry path and its attributes
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self {
+            main_attributes: vec![("Manifest-Version".to_owned(), "1.0".to_owned())],
+            sections: Vec::new(),
+        }
+    }
+
+    /// Sets attribute of the main section, e.g. `Main-Class`, replacing previous value
+    pub fn main_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        set_attribute(&mut self.main_attributes, name.into(), value.into());
+        self
+    }
+
+    /// Sets attribute in section of entry with `path`, e.g. `Sealed`, replacing previous value
+    pub fn entry_attribute(
+        mut self,
+        path: impl Into<String>,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        let path = path.into();
+        let index = match self.sections.iter().position(|(p, _)| *p == path) {
+            Some(index) => index,
+            None => {
+                self.sections.push((path, Vec::new()));
+                self.sections.len() - 1
+            }
+        };
+        set_attribute(&mut self.sections[index].1, name.into(), value.into());
+        self
+    }
+
+    /// Checks that attribute names consist of `A-Z`, `a-z`, `0-9`, `_` and `-` and aren't longer
+    /// than 70 bytes, values and entry paths don't contain CR, LF and NUL
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let sections = self
+            .sections
+            .iter()
+            .map(|(path, attributes)| (Some(path), attributes));
+        for (path, attributes) in Some((None, &self.main_attributes))
+            .into_iter()
+            .chain(sections)
+        {
+            if let Some(path) = path {
+                check_value(path)?;
+            }
+            for (name, value) in attributes {
+                check_name(name)?;
+                check_value(value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Manifest in JAR format: `Name: value` lines wrapped at 72 bytes
+    /// and sections separated by empty lines. It's validated, when JAR is written
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, value) in &self.main_attributes {
+            write_line(&mut out, name, value);
+        }
+        for (path, attributes) in &self.sections {
+            out.extend_from_slice(b"\r\n");
+            write_line(&mut out, "Name", path);
+            for (name, value) in attributes {
+                write_line(&mut out, name, value);
+            }
+        }
+        out.extend_from_slice(b"\r\n");
+
+        out
+    }
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn set_attribute(attributes: &mut Vec<(String, String)>, name: String, value: String) {
+    // attribute names are case insensitive
+    match attributes
+        .iter_mut()
+        .find(|(n, _)| n.eq_ignore_ascii_case(&name))
+    {
+        Some((_, v)) => *v = value,
+        None => attributes.push((name, value)),
+    }
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid manifest attribute name {:?}", name))
+    }
+}
+
+fn check_value(value: &str) -> Result<(), String> {
+    if value.contains(&['\r', '\n', '\0'][..]) {
+        Err(format!("invalid manifest value {:?}", value))
+    } else {
+        Ok(())
+    }
+}
+
+/// Writes `name: value` line, continuation lines start with space. Lines are broken
+/// only between characters, so every line is valid UTF-8
+fn write_line(out: &mut Vec<u8>, name: &str, value: &str) {
+    let mut line_length = 0;
+    let mut buf = [0; 4];
+    for c in name.chars().chain(": ".chars()).chain(value.chars()) {
+        let bytes = c.encode_utf8(&mut buf).as_bytes();
+        if line_length + bytes.len() > MAX_LINE_LENGTH {
+            out.extend_from_slice(b"\r\n ");
+            line_length = 1;
+        }
+        out.extend_from_slice(bytes);
+        line_length += bytes.len();
+    }
+    out.extend_from_slice(b"\r\n");
+}
@@ -1,36 +1,213 @@
-#![feature(test)]
+#![cfg_attr(test, feature(test))]
 
+#[cfg(feature = "chrono")]
+use chrono::DateTime;
+use std::convert::TryFrom;
 use std::fs::File;
-use std::io::{Read, Result, Write};
+use std::io::{self, Read, Seek, Write};
 use std::marker::PhantomData;
-
+use std::time::SystemTime;
 #[cfg(feature = "time")]
-use {std::time::SystemTime, time::OffsetDateTime};
+use time::OffsetDateTime;
 
+pub use crate::cancellation::CancellationToken;
+pub use crate::checkpoint::Checkpoint;
 use crate::compressor::{
     Compressor, CompressorConfig, HashWriteWrapper, Store, WriterWrapper, WriterWrapperOwned,
 };
+pub use crate::container::ContainerProfile;
+pub use crate::dos_time::TimeZone;
+use crate::encryption::EncryptionConfig;
+pub use crate::error::{Error, FormatLimit, Result};
+pub use crate::jar::Manifest;
+pub use crate::observer::{Event, Observer};
+pub use crate::path::{DuplicatePolicy, PathEncoding, PathError, PathPolicy, ZipPath};
+use crate::read::ZipArchive;
+pub use crate::recovery::{RecoveryReport, Truncate};
+pub use crate::summary::{EntrySummary, Summary};
+pub use crate::throttle::{RateLimit, RateLimiter, Throttled};
+use crate::zip_impl::HeaderPath;
 pub use crate::zip_impl::{Header, ZipWriter};
 
+mod append;
+mod cancellation;
+mod checkpoint;
 pub mod compressor;
+mod container;
+mod dir;
+mod dos_time;
+pub mod encryption;
+mod error;
+mod jar;
+mod observer;
+mod path;
+pub mod read;
+mod recovery;
+mod summary;
+mod throttle;
 mod zip_impl;
 
-impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
+impl<W: Write, P: ZipPath> ZipWriter<W, P> {
     pub fn new(write: W) -> ZipWriter<W, P> {
         Self {
             write,
             position: 0,
+            committed_position: 0,
             entries: vec![],
+            path_policy: PathPolicy::verbatim(),
+            duplicate_policy: DuplicatePolicy::Allow,
+            paths: None,
+            path_encoding: PathEncoding::Utf8,
+            observer: None,
+            progress_interval: observer::DEFAULT_PROGRESS_INTERVAL,
+            next_progress: 0,
+            entry_bytes_out: 0,
+            cancellation: None,
+            reproducible: None,
+            time_zone: TimeZone::Utc,
+            extended_timestamp: false,
+            container: None,
+            comment: Vec::new(),
+            pending_truncate: None,
+        }
+    }
+
+    /// Creates writer, which applies [PathPolicy::strict] to entry paths
+    pub fn new_strict(write: W) -> ZipWriter<W, P> {
+        Self::new(write).path_policy(PathPolicy::strict())
+    }
+
+    /// Makes output depend only on entries data and paths: modification time of every entry
+    /// is fixed to 1980-01-01 00:00:00. Other fields are host independent anyway: permissions
+    /// and owners aren't written and [ZipWriter::append_dir] sorts entries.
+    ///
+    /// Deflated entries are compressed with fixed level
+    /// [REPRODUCIBLE_LEVEL](compressor::deflate::REPRODUCIBLE_LEVEL), level set for entry is ignored.
+    /// Deflated data also depends on flate2 backend with its version.
+    /// flate2 uses miniz_oxide by default, but any crate in the build can switch it
+    /// to zlib with cargo feature unification, so for identical bytes across machines
+    /// build with the same `Cargo.lock` and flate2 features. Encrypted entries
+    /// aren't reproducible, because of random salt
+    pub fn reproducible(self) -> Self {
+        self.reproducible_dos(dos_time::DEFAULT_TIME, dos_time::DEFAULT_DATE)
+    }
+
+    /// Same as [ZipWriter::reproducible], but modification time is fixed to `date_time`
+    #[cfg(feature = "time")]
+    pub fn reproducible_at(self, date_time: OffsetDateTime) -> Self {
+        let (time, date) = dos_time::from_date_time(date_time);
+        self.reproducible_dos(time, date)
+    }
+
+    /// Same as [ZipWriter::reproducible_at], but with chrono
+    #[cfg(feature = "chrono")]
+    pub fn reproducible_at_chrono<Tz: chrono::TimeZone>(self, date_time: DateTime<Tz>) -> Self {
+        let (time, date) = dos_time::from_chrono(&date_time);
+        self.reproducible_dos(time, date)
+    }
+
+    /// Same as [ZipWriter::reproducible], but modification time is taken
+    /// from `SOURCE_DATE_EPOCH` environment variable, if it's set
+    pub fn reproducible_from_env(self) -> Self {
+        let epoch = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|epoch| epoch.trim().parse::<i64>().ok());
+
+        match epoch {
+            Some(epoch) => {
+                let (time, date) = dos_time::from_unix(epoch);
+                self.reproducible_dos(time, date)
+            }
+            None => self.reproducible(),
         }
     }
 
+    /// Sets time zone of MS-DOS date time fields of subsequent entries, [TimeZone::Utc] by default
+    pub fn time_zone(mut self, time_zone: TimeZone) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    /// Adds extended timestamp extra field (0x5455) with exact modification time in UTC
+    /// to subsequent entries, which have it set. Unzip tools, which understand it, restore
+    /// time regardless of time zone of MS-DOS fields. It's never written in reproducible mode
+    pub fn extended_timestamp(mut self, enabled: bool) -> Self {
+        self.extended_timestamp = enabled;
+        self
+    }
+
+    /// Makes archive follow layout of document format: profiles with `mimetype` entry write it
+    /// first (stored, without extra field and data descriptor), when the first entry is started
+    /// or archive is finished, and reject user `mimetype` entries. Other entries are rejected
+    /// with [Error::ContainerLayout], if they break required order.
+    /// [ContainerProfile::Jar] also sets [DuplicatePolicy::Error]
+    pub fn container(mut self, profile: ContainerProfile) -> Self {
+        let jar = matches!(profile, ContainerProfile::Jar(_));
+        self.container = Some(profile);
+        if jar {
+            self.duplicate_policy(DuplicatePolicy::Error)
+        } else {
+            self
+        }
+    }
+
+    fn reproducible_dos(mut self, time: u16, date: u16) -> Self {
+        self.reproducible = Some((time, date));
+        self
+    }
+
+    /// Sets token, which stops writing when cancelled, see [CancellationToken]
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sets receiver of entry and progress events
+    pub fn observer(mut self, observer: impl Observer + Send + 'static) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Sets number of entry bytes between [Event::Progress] events, 1 MiB by default
+    pub fn progress_interval(mut self, bytes: u64) -> Self {
+        self.progress_interval = bytes.max(1);
+        self
+    }
+
+    /// Sets rules applied to paths of subsequent entries, [PathPolicy::verbatim] by default.
+    /// Paths are checked when entry data starts being written,
+    /// rejected ones are returned as [Error::InvalidPath] and nothing is written into archive
+    pub fn path_policy(mut self, path_policy: PathPolicy) -> Self {
+        self.path_policy = path_policy;
+        self
+    }
+
+    /// Sets how paths of subsequent entries are encoded, [PathEncoding::Utf8] by default
+    pub fn path_encoding(mut self, path_encoding: PathEncoding) -> Self {
+        self.path_encoding = path_encoding;
+        self
+    }
+
+    /// Sets how entries with already written paths are handled, [DuplicatePolicy::Allow] by default.
+    /// Other policies keep index of written paths, which is checked when entry data starts being written
+    pub fn duplicate_policy(mut self, duplicate_policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = duplicate_policy;
+        self.paths = match duplicate_policy {
+            DuplicatePolicy::Allow => None,
+            _ => Some(
+                self.entries
+                    .iter()
+                    .map(|e| e.path_bytes().to_owned())
+                    .collect(),
+            ),
+        };
+        self
+    }
+
     pub fn append_file(&mut self, path: P, file: File) -> Result<()> {
-        #[cfg(feature = "time")]
         self.start_file(path)
             .modification_from_file(&file)
             .write_data(file)?;
-        #[cfg(not(feature = "time"))]
-        self.start_file(path).write_data(file)?;
 
         Ok(())
     }
@@ -47,35 +224,110 @@ impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
         Ok(())
     }
 
+    /// Copies entry `path` from `archive` without recompression.
+    /// Compressed data, compression method, crc and sizes are written verbatim,
+    /// only offsets are recalculated for the new archive
+    pub fn copy_entry_from<R: Read + Seek>(
+        &mut self,
+        archive: &mut ZipArchive<R>,
+        path: P,
+    ) -> Result<()> {
+        let source_error = |source| Error::Source {
+            path: String::from_utf8_lossy(path.as_path_bytes()).into_owned(),
+            source,
+        };
+        let index = archive.index_of(path.as_path_bytes()).ok_or_else(|| {
+            source_error(io::Error::new(
+                io::ErrorKind::NotFound,
+                "entry not found in archive",
+            ))
+        })?;
+        let entry = archive.entries()[index].clone();
+        if entry.is_encrypted() {
+            return Err(source_error(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "copying encrypted entries is not supported",
+            )));
+        }
+        let data = archive.raw_data(index).map_err(source_error)?;
+
+        let mut builder = self.start_file(path);
+        builder.header.modification_time = Some(entry.modification_time);
+        builder.header.modification_date = Some(entry.modification_date);
+        builder.write_raw(
+            entry.compression_id,
+            data,
+            entry.crc32,
+            entry.uncompressed_size,
+            Some(entry.compressed_size),
+        )?;
+
+        Ok(())
+    }
+
     pub fn start_file(
         &mut self,
         path: P,
     ) -> ZipEntryBuilder<P, ZipWriterWrapper<&mut Self, W, P>, compressor::StoreConfig> {
         ZipEntryBuilder {
-            header: Header::builder(),
+            header: self.header_builder(),
             writer: ZipWriterWrapper(self, PhantomData),
             compressor_config: compressor::StoreConfig,
         }
         .path(path)
     }
 
+    /// Starts entry with path, which isn't of user path type, e.g. found in file system
+    pub(crate) fn start_file_owned(
+        &mut self,
+        path: Vec<u8>,
+    ) -> ZipEntryBuilder<P, ZipWriterWrapper<&mut Self, W, P>, compressor::StoreConfig> {
+        let mut builder = ZipEntryBuilder {
+            header: self.header_builder(),
+            writer: ZipWriterWrapper(self, PhantomData),
+            compressor_config: compressor::StoreConfig,
+        };
+        builder.header.path = Some(HeaderPath::Owned(path));
+        builder
+    }
+
     pub fn start_file_writer(
         self,
         path: P,
     ) -> ZipEntryBuilder<P, ZipWriterWrapper<Self, W, P>, compressor::StoreConfig> {
         ZipEntryBuilder {
-            header: Header::builder(),
+            header: self.header_builder(),
             writer: ZipWriterWrapper(self, PhantomData),
             compressor_config: compressor::StoreConfig,
         }
         .path(path)
     }
 
+    /// Header builder with writer wide time settings
+    fn header_builder(&self) -> HeaderBuilder<P> {
+        let mut builder = Header::builder();
+        builder.time_zone = self.time_zone;
+        builder.extended_timestamp = self.extended_timestamp && self.reproducible.is_none();
+        builder.reproducible = self.reproducible.is_some();
+        builder
+    }
+
     pub fn finish(self) -> Result<W> {
         self.write_central_directory()
     }
 
-    /// Can return with incomplete data written in [W]
+    /// Headers of complete entries
+    pub fn entries(&self) -> &[Header<P>] {
+        &self.entries
+    }
+
+    /// Number of bytes taken by complete entries, data after it belongs to unfinished
+    /// or failed one and is discarded by [ZipWriter::recover]
+    pub fn committed_position(&self) -> u64 {
+        self.committed_position
+    }
+
+    /// Can return with incomplete data written in [W], see [ZipWriter::recover]
     pub fn into_inner(self) -> W {
         self.write
     }
@@ -83,54 +335,76 @@ impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
 
 /// It's needed to hide [Write] implementation on ZipWriter,
 /// so user can't screw up format by writing random bytes
-pub struct ZipWriterWrapper<T: AsMut<ZipWriter<W, P>>, W: Write, P: AsRef<str>>(
+pub struct ZipWriterWrapper<T: AsMut<ZipWriter<W, P>>, W: Write, P: ZipPath>(
     T,
     PhantomData<(W, P)>,
 );
 
-impl<T: AsMut<ZipWriter<W, P>>, W: Write, P: AsRef<str>> Write for ZipWriterWrapper<T, W, P> {
-    fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        self.0.as_mut().write.write(buf)
+impl<T: AsMut<ZipWriter<W, P>>, W: Write, P: ZipPath> Write for ZipWriterWrapper<T, W, P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let zip = self.0.as_mut();
+        let count = zip.write.write(buf).map_err(Error::sink)?;
+        zip.entry_bytes_out += count as u64;
+
+        Ok(count)
     }
 
-    fn flush(&mut self) -> Result<()> {
-        self.0.as_mut().write.flush()
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.as_mut().write.flush().map_err(Error::sink)
     }
 
-    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
-        self.0.as_mut().write.write_all(buf)
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let zip = self.0.as_mut();
+        zip.write.write_all(buf).map_err(Error::sink)?;
+        zip.entry_bytes_out += buf.len() as u64;
+
+        Ok(())
     }
 }
 
-impl<T: AsMut<ZipWriter<W, P>>, W: Write, P: AsRef<str>> WriterWrapper
-    for ZipWriterWrapper<T, W, P>
-{
+impl<T: AsMut<ZipWriter<W, P>>, W: Write, P: ZipPath> WriterWrapper for ZipWriterWrapper<T, W, P> {
     type Inner = T;
     type Path = P;
 
-    fn start_entry(&mut self, header: &mut Header<Self::Path>) -> Result<()> {
-        self.0.as_mut().write_entry_header(header)
+    fn start_entry(&mut self, header: &mut Header<Self::Path>) -> io::Result<()> {
+        Ok(self.0.as_mut().write_entry_header(header)?)
     }
 
-    fn end_entry(mut self, entry: Header<Self::Path>) -> Result<Self::Inner> {
+    fn end_entry(mut self, entry: Header<Self::Path>) -> io::Result<Self::Inner> {
         self.0.as_mut().position += entry.compressed_size;
         self.0.as_mut().write_entry_data_descriptor(&entry)?;
-        self.0.as_mut().entries.push(entry);
+        let zip = self.0.as_mut();
+        zip.committed_position = zip.position;
+        zip.notify_entry_end(&entry);
+        if entry.aborted {
+            zip.forget_path(&entry);
+        } else {
+            zip.entries.push(entry);
+        }
         Ok(self.0)
     }
+
+    fn progress(&mut self, bytes_in: u64) -> io::Result<()> {
+        self.0.as_mut().report_progress(bytes_in);
+        Ok(())
+    }
+
+    fn check_cancelled(&mut self) -> io::Result<()> {
+        Ok(self.0.as_mut().check_cancelled()?)
+    }
 }
 
 impl<W, P> WriterWrapperOwned for ZipWriterWrapper<ZipWriter<W, P>, W, P>
 where
     W: Write,
-    P: AsRef<str>,
+    P: ZipPath,
 {
 }
 
 pub struct ZipFileWriter<C, P, ZIP>
 where
     C: Compressor<Inner = ZIP>,
-    P: AsRef<str>,
+    P: ZipPath,
     ZIP: WriterWrapper<Path = P>,
 {
     inner: HashWriteWrapper<C>,
@@ -139,46 +413,127 @@ where
 
 impl<C, P, ZIP> ZipFileWriter<C, P, ZIP>
 where
-    P: AsRef<str>,
+    P: ZipPath,
     C: Compressor<Inner = ZIP>,
     ZIP: WriterWrapper<Path = P>,
 {
     pub fn finish(self) -> Result<ZIP::Inner> {
+        self.end(false)
+    }
+
+    /// Terminates entry without adding it into archive: compressed stream is finished
+    /// and data descriptor is written, so the rest of archive stays valid,
+    /// but entry is omitted from central directory.
+    /// Aborted entry's local header and data stay in the archive, so readers,
+    /// which scan local headers (e.g. [ZipStreamReader](crate::read::ZipStreamReader)), still return it
+    pub fn abort(self) -> Result<ZIP::Inner> {
+        self.end(true)
+    }
+
+    fn end(self, aborted: bool) -> Result<ZIP::Inner> {
         let (crc32, writer) = self.inner.finish();
-        let (entry_data, writer) = writer.finish()?;
+        let (entry_data, writer) = writer.finish().map_err(Error::compressor)?;
 
         let mut header = self.header;
 
         header.uncompressed_size = entry_data.uncompressed_size;
         header.compressed_size = entry_data.compressed_size;
         header.crc32 = crc32;
+        header.aborted = aborted;
 
-        writer.end_entry(header)
+        Ok(writer.end_entry(header)?)
     }
 }
 
 impl<C, P, ZIP> Write for ZipFileWriter<C, P, ZIP>
 where
     C: Compressor<Inner = ZIP>,
-    P: AsRef<str>,
+    P: ZipPath,
     ZIP: WriterWrapper<Path = P>,
 {
-    fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        self.inner.write(buf)
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        ProgressWriter(&mut self.inner).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        ProgressWriter(&mut self.inner).write_all(buf)
+    }
+}
+
+/// Reports number of received entry bytes down to [ZipWriter] after each write
+struct ProgressWriter<'a, C: Compressor>(&'a mut HashWriteWrapper<C>);
+
+impl<C: Compressor> ProgressWriter<'_, C> {
+    fn check_cancelled(&mut self) -> io::Result<()> {
+        match self.0.get_mut().get_mut() {
+            Some(inner) => inner.check_cancelled(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<C: Compressor> Write for ProgressWriter<'_, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_cancelled()?;
+        let count = self.0.write(buf)?;
+        let bytes_in = self.0.count();
+        if let Some(inner) = self.0.get_mut().get_mut() {
+            inner.progress(bytes_in)?;
+        }
+
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.check_cancelled()?;
+        self.0.write_all(buf)?;
+        let bytes_in = self.0.count();
+        match self.0.get_mut().get_mut() {
+            Some(inner) => inner.progress(bytes_in),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reports progress of raw entry data, which is written into [WriterWrapper] as is
+struct RawProgressWriter<'a, W: WriterWrapper> {
+    inner: &'a mut W,
+    bytes_in: u64,
+}
+
+impl<W: WriterWrapper> Write for RawProgressWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.check_cancelled()?;
+        let count = self.inner.write(buf)?;
+        self.bytes_in += count as u64;
+        self.inner.progress(self.bytes_in)?;
+
+        Ok(count)
     }
 
-    fn flush(&mut self) -> Result<()> {
+    fn flush(&mut self) -> io::Result<()> {
         self.inner.flush()
     }
 
-    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
-        self.inner.write_all(buf)
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner.check_cancelled()?;
+        self.inner.write_all(buf)?;
+        self.bytes_in += buf.len() as u64;
+        self.inner.progress(self.bytes_in)
     }
 }
 
 pub struct ZipEntryBuilder<P, W, CC = Store<W>>
 where
-    P: AsRef<str>,
+    P: ZipPath,
     W: WriterWrapper<Path = P>,
     CC: CompressorConfig<W>,
 {
@@ -189,7 +544,7 @@ where
 
 impl<P, W, CC> ZipEntryBuilder<P, W, CC>
 where
-    P: AsRef<str>,
+    P: ZipPath,
     W: WriterWrapper<Path = P>,
     CC: CompressorConfig<W>,
 {
@@ -204,34 +559,66 @@ where
         }
     }
 
+    /// Encrypts entry data after compression
+    pub fn encryption<E>(self, encryption_config: E) -> ZipEntryBuilder<P, E::EncryptorTarget, CC>
+    where
+        E: EncryptionConfig<W>,
+        CC: CompressorConfig<E::EncryptorTarget>,
+    {
+        ZipEntryBuilder {
+            compressor_config: self.compressor_config,
+            header: self.header,
+            writer: encryption_config.build(self.writer),
+        }
+    }
+
     pub fn path(mut self, path: P) -> Self {
-        self.header.path = Some(path);
+        self.header.path = Some(HeaderPath::Original(path));
         self
     }
 
-    #[cfg(feature = "time")]
+    /// Modification time, it's written in time zone of [ZipEntryBuilder::time_zone]
+    /// and clamped to range of MS-DOS date time: 1980-2107
     pub fn modification(self, time: SystemTime) -> Self {
-        let date_time = OffsetDateTime::from(time);
-        self.modification_date_time(date_time)
+        self.modification_unix(dos_time::unix_seconds(time))
+    }
+
+    /// Same as [ZipEntryBuilder::modification], but with seconds since Unix epoch
+    pub fn modification_unix(mut self, seconds: i64) -> Self {
+        self.header.modified = Some(seconds);
+        self.header.modification_time = None;
+        self.header.modification_date = None;
+        self
+    }
+
+    /// Overrides time zone of [ZipWriter::time_zone] for this entry
+    pub fn time_zone(mut self, time_zone: TimeZone) -> Self {
+        self.header.time_zone = time_zone;
+        self
     }
 
+    /// MS-DOS date time fields are written in offset of `date_time`, time zone setting is ignored
     #[cfg(feature = "time")]
     pub fn modification_date_time(mut self, date_time: OffsetDateTime) -> Self {
-        self.header.modification_time = Some(
-            (date_time.second() / 2) as u16 | // 0-4 bits
-                (date_time.minute() as u16) << 5 | // 5-10 bits
-                (date_time.hour() as u16) << 11, // 11-15 bits
-        );
-        self.header.modification_date = Some(
-            (date_time.day() as u16) | // 0-4 bits
-                ((date_time.month() as u16) << 5) | // 5-8 bits
-                ((date_time.year() - 1980) as u16) << 9, // 9-15 bits
-        );
+        let (time, date) = dos_time::from_date_time(date_time);
+        self.header.modification_time = Some(time);
+        self.header.modification_date = Some(date);
+        self.header.modified = Some(date_time.unix_timestamp());
+
+        self
+    }
+
+    /// Same as [ZipEntryBuilder::modification_date_time], but with chrono
+    #[cfg(feature = "chrono")]
+    pub fn modification_chrono<Tz: chrono::TimeZone>(mut self, date_time: DateTime<Tz>) -> Self {
+        let (time, date) = dos_time::from_chrono(&date_time);
+        self.header.modification_time = Some(time);
+        self.header.modification_date = Some(date);
+        self.header.modified = Some(date_time.timestamp());
 
         self
     }
 
-    #[cfg(feature = "time")]
     pub fn modification_from_file(self, file: &File) -> Self {
         let modified_at = file
             .metadata()
@@ -243,10 +630,17 @@ where
     }
 
     fn writer_inner(mut self) -> Result<ZipFileWriter<CC::CompressorTarget, P, W>> {
+        let compressor_config = if self.header.reproducible {
+            self.compressor_config.reproducible()
+        } else {
+            self.compressor_config
+        };
         let mut header = self.header.build::<CC, W>();
-        self.writer.start_entry(&mut header)?;
+        self.writer
+            .start_entry(&mut header)
+            .map_err(Error::compressor)?;
 
-        let compressor = self.compressor_config.build(self.writer);
+        let compressor = compressor_config.build(self.writer);
 
         Ok(ZipFileWriter {
             inner: HashWriteWrapper::new(compressor),
@@ -254,20 +648,116 @@ where
         })
     }
 
-    pub fn write_data(self, mut data: impl Read) -> Result<W::Inner> {
+    /// If reading `data` fails or writing is cancelled, entry is aborted (see [ZipFileWriter::abort])
+    /// and error is returned, so archive can be used further
+    pub fn write_data(self, data: impl Read) -> Result<W::Inner> {
         let mut writer = self.writer_inner()?;
 
-        std::io::copy(&mut data, &mut writer)?;
-
-        writer.finish()
+        let mut progress_writer = ProgressWriter(&mut writer.inner);
+        match copy_entry_data(&writer.header, data, &mut progress_writer, &mut 0) {
+            Ok(()) => writer.finish(),
+            Err(e @ Error::Source { .. }) | Err(e @ Error::Cancelled) => {
+                writer.abort()?;
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
     }
 
     pub fn write_all(self, data: &[u8]) -> Result<W::Inner> {
         let mut writer = self.writer_inner()?;
 
-        writer.write_all(data)?;
+        match writer.write_all(data).map_err(Error::compressor) {
+            Ok(()) => writer.finish(),
+            Err(Error::Cancelled) => {
+                writer.abort()?;
+                Err(Error::Cancelled)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes already deflated `data` as is, bypassing compressor and crc calculation.
+    /// `crc32` and `uncompressed_size` must describe original (uncompressed) data,
+    /// they are written into data descriptor and central directory verbatim.
+    pub fn write_raw_deflate(
+        self,
+        data: impl Read,
+        crc32: u32,
+        uncompressed_size: u64,
+    ) -> Result<W::Inner> {
+        self.write_raw(8, data, crc32, uncompressed_size, None)
+    }
+
+    pub(crate) fn write_raw(
+        mut self,
+        compression_id: u16,
+        data: impl Read,
+        crc32: u32,
+        uncompressed_size: u64,
+        compressed_size: Option<u64>,
+    ) -> Result<W::Inner> {
+        let mut header = self.header.build_raw(compression_id);
+        self.writer
+            .start_entry(&mut header)
+            .map_err(Error::compressor)?;
+
+        let mut written = 0;
+        let mut progress_writer = RawProgressWriter {
+            inner: &mut self.writer,
+            bytes_in: 0,
+        };
+        let mut result = copy_entry_data(&header, data, &mut progress_writer, &mut written);
+        if result.is_ok() && compressed_size.is_some_and(|size| size != written) {
+            result = Err(Error::Source {
+                path: header.path_lossy(),
+                source: io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "entry data is shorter than its compressed size",
+                ),
+            });
+        }
+        header.compressed_size = written;
+        header.uncompressed_size = uncompressed_size;
+        header.crc32 = crc32;
+
+        match result {
+            Ok(()) => Ok(self.writer.end_entry(header)?),
+            Err(e @ Error::Source { .. }) | Err(e @ Error::Cancelled) => {
+                header.aborted = true;
+                self.writer.end_entry(header)?;
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Same as [io::copy], but tells apart source and compressor errors.
+/// `written` is updated even if copying fails
+fn copy_entry_data<P: ZipPath>(
+    header: &Header<P>,
+    mut data: impl Read,
+    writer: &mut impl Write,
+    written: &mut u64,
+) -> Result<()> {
+    let mut buf = [0; 8 * 1024];
+
+    loop {
+        let count = match data.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(count) => count,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(source) => {
+                return Err(Error::Source {
+                    path: header.path_lossy(),
+                    source,
+                })
+            }
+        };
 
-        writer.finish()
+        writer.write_all(&buf[..count]).map_err(Error::compressor)?;
+        *written += count as u64;
     }
 }
 
@@ -292,7 +782,7 @@ where
 /// ```
 impl<P, W, CC> ZipEntryBuilder<P, W, CC>
 where
-    P: AsRef<str>,
+    P: ZipPath,
     W: WriterWrapper<Path = P> + WriterWrapperOwned,
     CC: CompressorConfig<W>,
 {
@@ -301,33 +791,76 @@ where
     }
 }
 
-pub struct HeaderBuilder<P: AsRef<str>> {
-    path: Option<P>,
+pub struct HeaderBuilder<P: ZipPath> {
+    path: Option<HeaderPath<P>>,
     modification_time: Option<u16>,
     modification_date: Option<u16>,
+    /// Seconds since Unix epoch, MS-DOS fields are computed from it, if they aren't set
+    modified: Option<i64>,
+    time_zone: TimeZone,
+    extended_timestamp: bool,
+    /// Compressor config is replaced with its reproducible version
+    reproducible: bool,
 }
 
-impl<P: AsRef<str>> HeaderBuilder<P> {
+impl<P: ZipPath> HeaderBuilder<P> {
     pub fn build<CC: CompressorConfig<W>, W: WriterWrapper>(self) -> Header<P> {
-        Header {
-            compression_id: CC::CompressorTarget::compression_id(),
+        self.build_raw(CC::CompressorTarget::compression_id())
+    }
+
+    pub(crate) fn build_raw(self, compression_id: u16) -> Header<P> {
+        let (modification_time, modification_date) = match (
+            self.modification_time,
+            self.modification_date,
+            self.modified,
+        ) {
+            (Some(time), Some(date), _) => (time, date),
+            (_, _, Some(seconds)) => dos_time::from_unix_in(seconds, self.time_zone),
+            _ => (dos_time::DEFAULT_TIME, dos_time::DEFAULT_DATE),
+        };
+
+        let mut header = Header {
+            compression_id,
+            flags: 0b0000_0000_0000_1000, // crc and sizes are in data descriptor
             path: self.path.unwrap(),
-            modification_date: self.modification_date.unwrap_or(0),
-            modification_time: self.modification_time.unwrap_or(0),
+            modification_date,
+            modification_time,
             compressed_size: 0,
             uncompressed_size: 0,
             crc32: 0,
             offset: 0,
+            extra_field: Vec::new(),
+            version_made_by: None,
+            internal_attributes: 0,
+            external_attributes: 0,
+            comment: Vec::new(),
+            aborted: false,
+        };
+        // extended timestamp (0x5455) with modification time only,
+        // so local and central directory versions are the same
+        let mtime = self
+            .modified
+            .and_then(|seconds| i32::try_from(seconds).ok());
+        if let (true, Some(mtime)) = (self.extended_timestamp, mtime) {
+            let mut data = vec![0b1]; // mtime is present
+            data.extend_from_slice(&mtime.to_le_bytes());
+            header.add_extra_field(0x5455, &data);
         }
+
+        header
     }
 }
 
-impl<P: AsRef<str>> Header<P> {
+impl<P: ZipPath> Header<P> {
     pub fn builder() -> HeaderBuilder<P> {
         HeaderBuilder {
             path: None,
             modification_date: None,
             modification_time: None,
+            modified: None,
+            time_zone: TimeZone::Utc,
+            extended_timestamp: false,
+            reproducible: false,
         }
     }
 }
@@ -337,14 +870,20 @@ mod tests {
     extern crate test;
 
     use std::fs::File;
-    use std::io::{repeat, Cursor, Read, Write};
+    use std::io::{repeat, Cursor, Read, Seek, SeekFrom, Write};
+    use std::time::{Duration, Instant};
     use test::Bencher;
 
     use zip::write::FileOptions;
     use zip::CompressionMethod;
 
     use crate::compressor::deflate::DeflateConfig;
-    use crate::ZipWriter;
+    use crate::read::{ZipArchive, ZipStreamReader};
+    use crate::{
+        CancellationToken, Checkpoint, ContainerProfile, DuplicatePolicy, Error, Event,
+        FormatLimit, Manifest, PathEncoding, PathError, PathPolicy, RateLimit, RateLimiter,
+        Throttled, TimeZone, Truncate, ZipWriter,
+    };
 
     #[cfg_attr(target_os = "linux", test)]
     #[allow(dead_code)]
@@ -377,7 +916,7 @@ mod tests {
         let mut out = Cursor::new(Vec::new());
 
         let mut writer = ZipWriter::new(&mut out);
-        writer.append("test", &*data as &[u8]).unwrap();
+        writer.append("test", data as &[u8]).unwrap();
         writer.finish().unwrap();
 
         out.set_position(0);
@@ -389,11 +928,10 @@ mod tests {
             assert_eq!(file_names.next(), None);
         }
 
-        let file = archive.by_index(0).unwrap();
-        assert!(file
-            .bytes()
-            .map(Result::unwrap)
-            .eq((data as &[u8]).bytes().map(Result::unwrap)));
+        let mut file = archive.by_index(0).unwrap();
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).unwrap();
+        assert_eq!(content, data);
     }
 
     #[test]
@@ -418,11 +956,10 @@ mod tests {
             assert_eq!(file_names.next(), None);
         }
 
-        let file = archive.by_index(0).unwrap();
-        assert!(file
-            .bytes()
-            .map(Result::unwrap)
-            .eq((data as &[u8]).bytes().map(Result::unwrap)));
+        let mut file = archive.by_index(0).unwrap();
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).unwrap();
+        assert_eq!(content, data);
     }
 
     #[test]
@@ -446,6 +983,1537 @@ mod tests {
         assert_eq!(out1, out2);
     }
 
+    #[test]
+    fn raw_deflate() {
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), DeflateConfig::best());
+        encoder.write_all(data).unwrap();
+        let deflated = encoder.finish().unwrap();
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("test")
+            .write_raw_deflate(&*deflated, crc32fast::hash(data), data.len() as u64)
+            .unwrap();
+        let mut out = writer.finish().unwrap();
+
+        out.set_position(0);
+
+        let mut archive = zip::ZipArchive::new(&mut out).unwrap();
+        let mut file = archive.by_index(0).unwrap();
+        assert_eq!(file.compression(), CompressionMethod::Deflated);
+        assert_eq!(file.compressed_size(), deflated.len() as u64);
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).unwrap();
+        assert_eq!(content, data);
+    }
+
+    #[test]
+    fn copy_entry() {
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.append_data("stored", data).unwrap();
+        writer
+            .start_file("deflated")
+            .compression(DeflateConfig::best())
+            .write_all(data)
+            .unwrap();
+        let source = writer.finish().unwrap();
+
+        let mut archive = ZipArchive::new(source).unwrap();
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.append_data("new", b"New entry").unwrap();
+        writer.copy_entry_from(&mut archive, "deflated").unwrap();
+        writer.copy_entry_from(&mut archive, "stored").unwrap();
+        assert!(writer.copy_entry_from(&mut archive, "missing").is_err());
+        let mut out = writer.finish().unwrap();
+
+        out.set_position(0);
+
+        let mut archive = zip::ZipArchive::new(&mut out).unwrap();
+        assert_eq!(archive.len(), 3);
+        for name in ["deflated", "stored"] {
+            let mut file = archive.by_name(name).unwrap();
+            let mut content = Vec::new();
+            file.read_to_end(&mut content).unwrap();
+            assert_eq!(content, data);
+        }
+        assert_eq!(
+            archive.by_name("deflated").unwrap().compression(),
+            CompressionMethod::Deflated
+        );
+    }
+
+    #[test]
+    fn copy_entry_truncated() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.append_data("stored", b"Simple Test").unwrap();
+        let mut source = writer.finish().unwrap().into_inner();
+
+        // Claim more compressed data than the source holds
+        let central = source
+            .windows(4)
+            .position(|window| window == [0x50, 0x4b, 0x01, 0x02])
+            .unwrap();
+        source[central + 20..central + 24].copy_from_slice(&0x10000u32.to_le_bytes());
+
+        let mut archive = ZipArchive::new(Cursor::new(source)).unwrap();
+        assert_eq!(
+            archive.raw_data(1).unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        match writer.copy_entry_from(&mut archive, "stored") {
+            Err(Error::Source { path, source }) => {
+                assert_eq!(path, "stored");
+                assert_eq!(source.kind(), std::io::ErrorKind::UnexpectedEof);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let mut out = writer.finish().unwrap();
+
+        out.set_position(0);
+        let archive = zip::ZipArchive::new(&mut out).unwrap();
+        assert_eq!(archive.len(), 0);
+    }
+
+    #[test]
+    fn stream_reader() {
+        let data = b"Simple Test PK\x07\x08 Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(Vec::new());
+        writer.append_data("stored", data).unwrap();
+        writer
+            .start_file("deflated")
+            .compression(DeflateConfig::best())
+            .write_all(data)
+            .unwrap();
+        writer.append_data("skipped", data).unwrap();
+        writer.append_data("empty", b"").unwrap();
+        let out = writer.finish().unwrap();
+
+        let mut reader = ZipStreamReader::new(&out[..]);
+        for name in ["stored", "deflated"] {
+            let mut entry = reader.next_entry().unwrap().unwrap();
+            assert_eq!(entry.header().path(), name.as_bytes());
+
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            assert_eq!(content, data);
+            assert_eq!(entry.header().uncompressed_size(), Some(data.len() as u64));
+            assert_eq!(entry.header().crc32(), crc32fast::hash(data));
+        }
+        assert_eq!(
+            reader.next_entry().unwrap().unwrap().header().path(),
+            b"skipped"
+        );
+
+        let mut entry = reader.next_entry().unwrap().unwrap();
+        assert_eq!(entry.header().path(), b"empty");
+        assert_eq!(entry.read(&mut [0; 16]).unwrap(), 0);
+
+        assert!(reader.next_entry().unwrap().is_none());
+    }
+
+    #[test]
+    fn stream_reader_zip64_descriptor() {
+        // empty entry with ZIP64 extra and 64-bit data descriptor, zero sizes fit 32-bit one too
+        let mut out = Vec::new();
+        out.extend_from_slice(b"PK\x03\x04\x2D\x00\x08\x00\x00\x00");
+        out.extend_from_slice(&[0; 8]); // time, date and crc
+        out.extend_from_slice(&[0xFF; 8]); // sizes
+        out.extend_from_slice(&[1, 0, 20, 0]); // path and extra field lengths
+        out.extend_from_slice(b"a\x01\x00\x10\x00");
+        out.extend_from_slice(&[0; 16]);
+        out.extend_from_slice(b"PK\x07\x08");
+        out.extend_from_slice(&[0; 20]);
+        let mut writer = ZipWriter::new(Vec::new());
+        writer.append_data("b", b"Simple Test").unwrap();
+        out.extend_from_slice(&writer.finish().unwrap());
+
+        let mut reader = ZipStreamReader::new(&out[..]);
+        let mut entry = reader.next_entry().unwrap().unwrap();
+        assert_eq!(entry.header().path(), b"a");
+        assert_eq!(entry.read(&mut [0; 16]).unwrap(), 0);
+
+        let mut entry = reader.next_entry().unwrap().unwrap();
+        assert_eq!(entry.header().path(), b"b");
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).unwrap();
+        assert_eq!(content, b"Simple Test");
+        assert!(reader.next_entry().unwrap().is_none());
+    }
+
+    #[test]
+    fn stream_reader_crc_mismatch() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer.append_data("test", b"Simple Test").unwrap();
+        let mut out = writer.finish().unwrap();
+
+        let position = out.windows(6).position(|w| w == b"Simple").unwrap();
+        out[position] = b's';
+
+        let mut reader = ZipStreamReader::new(&out[..]);
+        let mut entry = reader.next_entry().unwrap().unwrap();
+        assert!(entry.read_to_end(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn archive_reader() {
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.append_data("stored", data).unwrap();
+        writer
+            .start_file("dir/deflated")
+            .compression(DeflateConfig::best())
+            .write_all(data)
+            .unwrap();
+        let out = writer.finish().unwrap();
+
+        let mut archive = ZipArchive::new(out).unwrap();
+        assert_eq!(archive.len(), 2);
+
+        let entry = archive.entry(0).unwrap();
+        assert_eq!(entry.path_str(), Some("stored"));
+        assert_eq!(entry.compression_id(), 0);
+        assert_eq!(entry.uncompressed_size(), data.len() as u64);
+        assert_eq!(entry.crc32(), crc32fast::hash(data));
+
+        let entry = archive.entry_by_name("dir/deflated").unwrap();
+        assert_eq!(entry.compression_id(), 8);
+        assert!(entry.compressed_size() < entry.uncompressed_size());
+
+        for name in ["stored", "dir/deflated"] {
+            let mut content = Vec::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            assert_eq!(content, data);
+        }
+        assert!(archive.by_name("missing").is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn archive_reader_modification() {
+        let date_time = time::Date::from_calendar_date(2021, time::Month::October, 9)
+            .unwrap()
+            .with_hms(12, 34, 56)
+            .unwrap()
+            .assume_utc();
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("test")
+            .modification_date_time(date_time)
+            .write_all(b"Simple Test")
+            .unwrap();
+        assert_eq!(
+            writer.entries()[0].modification_date_time(),
+            Some(date_time)
+        );
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+
+        assert_eq!(
+            archive.entry(0).unwrap().modification_date_time(),
+            Some(date_time)
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn archive_reader_modification_chrono() {
+        use chrono::{FixedOffset, TimeZone, Utc};
+
+        let date_time = Utc.with_ymd_and_hms(2021, 10, 9, 12, 34, 56).unwrap();
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new())).extended_timestamp(true);
+        writer
+            .start_file("test")
+            .modification_chrono(date_time)
+            .write_all(b"Simple Test")
+            .unwrap();
+        assert_eq!(
+            writer.entries()[0].modification_chrono(),
+            Some(date_time.naive_utc())
+        );
+        assert_eq!(
+            writer.entries()[0].modification_utc_chrono(),
+            Some(date_time)
+        );
+
+        // MS-DOS fields are in offset of date time, extended timestamp is in UTC
+        let local = FixedOffset::east_opt(2 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2021, 10, 9, 14, 34, 56)
+            .unwrap();
+        writer
+            .start_file("offset")
+            .modification_chrono(local)
+            .write_all(b"Simple Test")
+            .unwrap();
+        let (_, extended) = writer.entries()[1]
+            .extra_fields()
+            .find(|(id, _)| *id == 0x5455)
+            .unwrap();
+        assert_eq!(extended[1..], 1_633_782_896i32.to_le_bytes());
+
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            archive.entry(0).unwrap().modification_utc_chrono(),
+            Some(date_time)
+        );
+        let entry = archive.entry(1).unwrap();
+        assert_eq!(entry.modification_chrono(), Some(local.naive_local()));
+        assert_eq!(entry.modification_utc_chrono(), Some(date_time));
+
+        // without extended timestamp the exact time is unknown
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .start_file("test")
+            .modification_chrono(local)
+            .write_all(b"Simple Test")
+            .unwrap();
+        assert_eq!(
+            writer.entries()[0].modification_chrono(),
+            Some(local.naive_local())
+        );
+        assert_eq!(writer.entries()[0].modification_utc_chrono(), None);
+
+        let writer: ZipWriter<_, &str> = ZipWriter::new(Vec::new()).reproducible_at_chrono(local);
+        assert_eq!(
+            writer.reproducible,
+            Some(crate::dos_time::from_chrono(&local))
+        );
+    }
+
+    #[test]
+    fn archive_reader_foreign() {
+        let mut out = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::write::ZipWriter::new(&mut out);
+            writer.set_comment("archive comment");
+            writer
+                .start_file(
+                    "test",
+                    FileOptions::default().compression_method(CompressionMethod::Deflated),
+                )
+                .unwrap();
+            writer.write_all(b"Simple Test").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let mut archive = ZipArchive::new(out).unwrap();
+        assert_eq!(archive.comment(), b"archive comment");
+
+        let mut content = Vec::new();
+        archive
+            .by_index(0)
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, b"Simple Test");
+    }
+
+    #[test]
+    fn archive_reader_malformed_extra_field() {
+        let mut writer = ZipWriter::new(Vec::new()).extended_timestamp(true);
+        writer
+            .start_file("test")
+            .modification_unix(1_633_782_896)
+            .write_all(b"Simple Test")
+            .unwrap();
+        let mut out = writer.finish().unwrap();
+
+        // data length of extended timestamp in central directory record
+        let record = out.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        out[record + 46 + 4 + 2] = 0xFF;
+        let error = ZipArchive::new(Cursor::new(out)).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "zip-crypto")]
+    #[test]
+    fn zip_crypto_encryption() {
+        use crate::encryption::ZipCryptoConfig;
+
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("deflated")
+            .compression(DeflateConfig::best())
+            .encryption(ZipCryptoConfig::new("password"))
+            .write_all(data)
+            .unwrap();
+        writer
+            .start_file("stored")
+            .encryption(ZipCryptoConfig::new("password"))
+            .write_all(data)
+            .unwrap();
+        let mut out = writer.finish().unwrap();
+
+        out.set_position(0);
+
+        let mut archive = zip::ZipArchive::new(&mut out).unwrap();
+        assert!(archive
+            .by_name_decrypt("stored", b"wrong")
+            .unwrap()
+            .is_err());
+        for name in ["deflated", "stored"] {
+            let mut file = archive.by_name_decrypt(name, b"password").unwrap().unwrap();
+            let mut content = Vec::new();
+            file.read_to_end(&mut content).unwrap();
+            assert_eq!(content, data);
+        }
+    }
+
+    #[cfg(feature = "aes-crypto")]
+    #[test]
+    fn aes_encryption() {
+        use crate::encryption::AesConfig;
+
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("test")
+            .compression(DeflateConfig::best())
+            .encryption(AesConfig::new("password"))
+            .write_all(data)
+            .unwrap();
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+
+        let entry = archive.entry(0).unwrap().clone();
+        assert!(entry.is_encrypted());
+        assert_eq!(entry.compression_id(), 99);
+        assert_eq!(entry.crc32(), 0);
+        assert_eq!(
+            entry.extra_fields().find(|(id, _)| *id == 0x9901),
+            Some((0x9901, &[2, 0, b'A', b'E', 3, 8, 0][..]))
+        );
+
+        let out = archive.into_inner().into_inner();
+        // AE-x requires version 5.1 to extract, also in ZIP64 end of central directory
+        assert_eq!(out[4..6], [0x33, 0]);
+        let record = out.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        assert_eq!(out[record + 6..record + 8], [0x33, 0]);
+        let zip64_end = out.windows(4).position(|w| w == b"PK\x06\x06").unwrap();
+        assert_eq!(out[zip64_end + 14..zip64_end + 16], [0x33, 0]);
+
+        let mut archive = zip_aes::ZipArchive::new(Cursor::new(out)).unwrap();
+        assert!(archive.by_index_decrypt(0, b"wrong").unwrap().is_err());
+        let mut content = Vec::new();
+        archive
+            .by_index_decrypt(0, b"password")
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, data);
+    }
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("failing"))
+        }
+    }
+
+    impl Write for Failing {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("failing"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn typed_errors() {
+        let mut writer = ZipWriter::new(Vec::new());
+        match writer.append("source", Failing) {
+            Err(Error::Source { path, .. }) => assert_eq!(path, "source"),
+            e => panic!("unexpected result {:?}", e),
+        }
+
+        let long_path = "a".repeat(70000);
+        match writer.append_data(&long_path, b"") {
+            Err(Error::FormatLimit(FormatLimit::PathTooLong(70000))) => {}
+            e => panic!("unexpected result {:?}", e),
+        }
+
+        let mut writer = ZipWriter::new(Failing);
+        assert!(matches!(
+            writer.append_data("sink", b"data"),
+            Err(Error::Sink(_))
+        ));
+        assert!(matches!(
+            writer
+                .start_file("sink")
+                .compression(DeflateConfig::best())
+                .write_data(b"data" as &[u8]),
+            Err(Error::Sink(_))
+        ));
+
+        let e = std::io::Error::from(Error::FormatLimit(FormatLimit::PathTooLong(70000)));
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn abort_entry() {
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer =
+            ZipWriter::new(Cursor::new(Vec::new())).duplicate_policy(DuplicatePolicy::Error);
+        writer.append_data("first", data).unwrap();
+        assert!(matches!(
+            writer
+                .start_file("failed")
+                .compression(DeflateConfig::best())
+                .write_data(data.chain(Failing)),
+            Err(Error::Source { .. })
+        ));
+        assert!(writer
+            .start_file("raw")
+            .write_raw_deflate(data.chain(Failing), 0, 0)
+            .is_err());
+        writer.append_data("failed", data).unwrap();
+
+        let mut file_writer = writer.start_file_writer("aborted").writer().unwrap();
+        file_writer.write_all(data).unwrap();
+        let mut out = file_writer.abort().unwrap().finish().unwrap();
+
+        out.set_position(0);
+
+        let mut archive = zip::ZipArchive::new(&mut out).unwrap();
+        assert_eq!(archive.file_names().count(), 2);
+        for name in ["first", "failed"] {
+            let mut content = Vec::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            assert_eq!(content, data);
+        }
+
+        // aborted entries stay in the stream, only central directory omits them
+        let writer = ZipWriter::new(Vec::new());
+        let mut file_writer = writer.start_file_writer("aborted").writer().unwrap();
+        file_writer.write_all(data).unwrap();
+        let mut writer = file_writer.abort().unwrap();
+        writer.append_data("last", data).unwrap();
+        let out = writer.finish().unwrap();
+
+        let mut reader = ZipStreamReader::new(&out[..]);
+        for name in ["aborted", "last"] {
+            let mut entry = reader.next_entry().unwrap().unwrap();
+            assert_eq!(entry.header().path(), name.as_bytes());
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            assert_eq!(content, data);
+        }
+        assert!(reader.next_entry().unwrap().is_none());
+    }
+
+    /// Fails once, when data reaches `fail_at` position
+    struct FlakySink {
+        inner: Cursor<Vec<u8>>,
+        fail_at: Option<u64>,
+    }
+
+    impl Write for FlakySink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            match self.fail_at {
+                Some(fail_at) if self.inner.position() + buf.len() as u64 > fail_at => {
+                    let count = (fail_at - self.inner.position()) as usize;
+                    self.inner.write_all(&buf[..count])?;
+                    self.fail_at = None;
+                    Err(std::io::Error::other("flaky"))
+                }
+                _ => self.inner.write(buf),
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for FlakySink {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    impl Truncate for FlakySink {
+        fn truncate(&mut self, len: u64) -> std::io::Result<()> {
+            self.inner.truncate(len)
+        }
+    }
+
+    #[test]
+    fn recover() {
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(FlakySink {
+            inner: Cursor::new(Vec::new()),
+            fail_at: Some(150),
+        });
+        writer.append_data("first", data).unwrap();
+        let committed_position = writer.committed_position();
+        assert!(matches!(
+            writer.append_data("second", data),
+            Err(Error::Sink(_))
+        ));
+
+        let (sink, report) = writer.recover().unwrap();
+        assert_eq!(report.entries().collect::<Vec<_>>(), [b"first"]);
+        assert_eq!(report.committed_position(), committed_position);
+        assert_eq!(report.discarded(), 150 - committed_position);
+
+        let mut archive = ZipArchive::new(sink.inner).unwrap();
+        assert_eq!(archive.len(), 1);
+        let mut content = Vec::new();
+        archive
+            .by_name("first")
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, data);
+    }
+
+    #[test]
+    fn resume() {
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.append_data("first", data).unwrap();
+        writer
+            .start_file("second")
+            .compression(DeflateConfig::best())
+            .write_all(data)
+            .unwrap();
+        let mut checkpoint = Vec::new();
+        writer.checkpoint().write_to(&mut checkpoint).unwrap();
+        // partially written entry, which is lost with the process
+        let mut sink = writer.into_inner();
+        sink.write_all(b"PK\x03\x04 partial").unwrap();
+
+        let checkpoint = Checkpoint::read_from(&checkpoint[..]).unwrap();
+        assert_eq!(checkpoint.entries_count(), 2);
+        let mut writer = ZipWriter::resume(sink, checkpoint).unwrap();
+        assert!(matches!(
+            writer.append_data("../x", data),
+            Err(Error::InvalidPath { .. })
+        ));
+        writer.append_data("third", data).unwrap();
+        let mut out = writer.finish().unwrap();
+
+        out.set_position(0);
+
+        let mut archive = zip::ZipArchive::new(&mut out).unwrap();
+        assert_eq!(archive.len(), 3);
+        for name in ["first", "second", "third"] {
+            let mut content = Vec::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            assert_eq!(content, data);
+        }
+    }
+
+    #[test]
+    fn open_append() {
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("deflated")
+            .compression(DeflateConfig::best())
+            .write_all(data)
+            .unwrap();
+        let mut sink = writer.finish().unwrap();
+
+        // writer, which is dropped without writing, doesn't modify archive
+        let original = sink.get_ref().clone();
+        drop(ZipWriter::<_, &str>::open_append(&mut sink).unwrap());
+        assert_eq!(sink.get_ref(), &original);
+        assert_eq!(zip::ZipArchive::new(&mut sink).unwrap().len(), 1);
+
+        let mut writer = ZipWriter::open_append(sink).unwrap();
+        assert!(matches!(
+            writer.append_data("../x", data),
+            Err(Error::InvalidPath { .. })
+        ));
+        writer.append_data("stored", data).unwrap();
+        let sink = writer.finish().unwrap();
+
+        let mut out = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::write::ZipWriter::new(&mut out);
+            writer
+                .start_file("foreign", FileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+            writer.finish().unwrap();
+        }
+        let mut writer = ZipWriter::open_append(out).unwrap();
+        writer.append_data("stored", data).unwrap();
+        let foreign = writer.finish().unwrap();
+
+        for (mut out, names) in [
+            (sink, &["deflated", "stored"]),
+            (foreign, &["foreign", "stored"]),
+        ] {
+            out.set_position(0);
+
+            let mut archive = zip::ZipArchive::new(&mut out).unwrap();
+            assert_eq!(archive.len(), 2);
+            for name in names {
+                let mut content = Vec::new();
+                archive
+                    .by_name(name)
+                    .unwrap()
+                    .read_to_end(&mut content)
+                    .unwrap();
+                assert_eq!(content, data);
+            }
+        }
+    }
+
+    #[test]
+    fn open_append_metadata() {
+        let mut out = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::write::ZipWriter::new(&mut out);
+            writer.set_comment("Archive comment");
+            writer
+                .start_file("script", FileOptions::default().unix_permissions(0o755))
+                .unwrap();
+            writer.write_all(b"#!/bin/sh").unwrap();
+            writer.finish().unwrap();
+        }
+        let original = ZipArchive::new(out).unwrap();
+        let entry = original.entry(0).unwrap().clone();
+        let out = original.into_inner();
+
+        // attributes and comments survive both appending and resuming from checkpoint
+        let mut writer = ZipWriter::open_append(out).unwrap();
+        writer.append_data("stored", b"Simple Test").unwrap();
+        let mut checkpoint = Vec::new();
+        writer.checkpoint().write_to(&mut checkpoint).unwrap();
+        let checkpoint = Checkpoint::read_from(&checkpoint[..]).unwrap();
+        let writer = ZipWriter::<_, &str>::resume(writer.into_inner(), checkpoint).unwrap();
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+
+        assert_eq!(archive.comment(), b"Archive comment");
+        let appended = archive.entry(0).unwrap();
+        assert_eq!(appended.version_made_by(), entry.version_made_by());
+        assert_eq!(appended.external_attributes(), entry.external_attributes());
+        assert_eq!(appended.internal_attributes(), entry.internal_attributes());
+        assert_eq!(archive.entry(1).unwrap().external_attributes(), 0);
+
+        let mut out = archive.into_inner();
+        out.set_position(0);
+        let mut archive = zip::ZipArchive::new(&mut out).unwrap();
+        assert_eq!(archive.comment(), b"Archive comment");
+        assert_eq!(
+            archive.by_name("script").unwrap().unix_mode(),
+            Some(0o100755)
+        );
+
+        assert!(matches!(
+            ZipWriter::<_, &str>::open_append(Cursor::new(b"not an archive".to_vec())),
+            Err(Error::InvalidArchive(_))
+        ));
+    }
+
+    #[test]
+    fn observer() {
+        #[derive(Debug, PartialEq)]
+        enum Observed {
+            Started(Vec<u8>),
+            Progress(u64),
+            Finished(Vec<u8>, u64),
+            Aborted(Vec<u8>),
+            Written(u64),
+        }
+
+        fn observed(event: Event) -> Observed {
+            match event {
+                Event::EntryStarted { path } => Observed::Started(path.to_vec()),
+                Event::Progress { bytes_in, .. } => Observed::Progress(bytes_in),
+                Event::EntryFinished { path, data, .. } => {
+                    Observed::Finished(path.to_vec(), data.uncompressed_size)
+                }
+                Event::EntryAborted { path } => Observed::Aborted(path.to_vec()),
+                Event::CentralDirectoryWritten { entries, .. } => Observed::Written(entries),
+            }
+        }
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let observer_events = events.clone();
+        let mut writer = ZipWriter::new(Vec::new())
+            .observer(move |event: Event| observer_events.lock().unwrap().push(observed(event)))
+            .progress_interval(4096);
+
+        writer
+            .start_file("test")
+            .compression(DeflateConfig::best())
+            .write_data(repeat(b'a').take(10000))
+            .unwrap();
+        writer
+            .start_file("raw")
+            .write_raw_deflate(repeat(b'b').take(10000), 0, 10000)
+            .unwrap();
+        assert!(writer.append("failed", Failing).is_err());
+        writer.finish().unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                Observed::Started(b"test".to_vec()),
+                Observed::Progress(8192),
+                Observed::Finished(b"test".to_vec(), 10000),
+                Observed::Started(b"raw".to_vec()),
+                Observed::Progress(8192),
+                Observed::Finished(b"raw".to_vec(), 10000),
+                Observed::Started(b"failed".to_vec()),
+                Observed::Aborted(b"failed".to_vec()),
+                Observed::Written(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let data = b"Simple Test Simple Test Simple Test" as &[u8];
+
+        let mut writer = ZipWriter::new(Vec::new());
+        writer.append_data("stored", data).unwrap();
+        writer
+            .start_file("deflated")
+            .compression(DeflateConfig::best())
+            .write_all(data)
+            .unwrap();
+        let entry = &writer.entries()[1];
+        assert_eq!(entry.path_str(), Some("deflated"));
+        assert_eq!(entry.compression_id(), 8);
+        assert_eq!(entry.uncompressed_size(), data.len() as u64);
+        assert_eq!(entry.crc32(), crc32fast::hash(data));
+        let (out, summary) = writer.finish_with_summary().unwrap();
+
+        let archive = ZipArchive::new(Cursor::new(out)).unwrap();
+        assert_eq!(summary.entries.len(), 2);
+        for (summary, entry) in summary.entries.iter().zip(archive.entries()) {
+            assert_eq!(summary.path.as_bytes(), entry.path());
+            assert_eq!(summary.offset, entry.offset());
+            assert_eq!(summary.compression_id, entry.compression_id());
+            assert_eq!(summary.compressed_size, entry.compressed_size());
+            assert_eq!(summary.crc32, entry.crc32());
+        }
+        assert_eq!(summary.total_uncompressed_size, 2 * data.len() as u64);
+        assert_eq!(summary.entries[0].modified, "1980-01-01T00:00:00");
+
+        let mut summary = summary;
+        summary.entries[0].path = "a,\"b\"".to_owned();
+        let mut csv = Vec::new();
+        summary.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.split_terminator("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("path,offset,compression_id,"));
+        assert_eq!(
+            lines[1],
+            format!(
+                "\"a,\"\"b\"\"\",0,0,35,35,{},0,33,1980-01-01T00:00:00",
+                crc32fast::hash(data)
+            )
+        );
+        assert!(lines[2].starts_with("deflated,"));
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&summary).unwrap();
+            assert_eq!(
+                serde_json::from_str::<crate::Summary>(&json).unwrap(),
+                summary
+            );
+        }
+
+        // leading entries of container, which are written on finish, are included too
+        let writer: ZipWriter<_, &str> =
+            ZipWriter::new(Vec::new()).container(ContainerProfile::Jar(Manifest::new()));
+        let (out, summary) = writer.finish_with_summary().unwrap();
+        let archive = ZipArchive::new(Cursor::new(out)).unwrap();
+        let paths = summary
+            .entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["META-INF/", "META-INF/MANIFEST.MF"]);
+        assert_eq!(archive.len(), 2);
+        assert_eq!(
+            summary.central_directory_offset,
+            archive.central_directory_offset()
+        );
+    }
+
+    #[test]
+    fn cancellation() {
+        struct Cancelling(CancellationToken);
+
+        impl Read for Cancelling {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.0.cancel();
+                buf[0] = b'a';
+                Ok(1)
+            }
+        }
+
+        let token = CancellationToken::new();
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new())).cancellation(token.clone());
+        writer.append_data("first", b"data").unwrap();
+        assert!(matches!(
+            writer
+                .start_file("cancelled")
+                .compression(DeflateConfig::best())
+                .write_data(Cancelling(token.clone())),
+            Err(Error::Cancelled)
+        ));
+        assert!(matches!(
+            writer.append_data("second", b"data"),
+            Err(Error::Cancelled)
+        ));
+        let mut out = writer.finish().unwrap();
+
+        out.set_position(0);
+
+        let mut archive = zip::ZipArchive::new(&mut out).unwrap();
+        assert_eq!(archive.len(), 1);
+        let mut content = Vec::new();
+        archive
+            .by_name("first")
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, b"data");
+
+        // cancelled write doesn't consume data
+        let token = CancellationToken::new();
+        let writer = ZipWriter::new(Cursor::new(Vec::new())).cancellation(token.clone());
+        let mut file_writer = writer.start_file_writer("cancelled").writer().unwrap();
+        assert_eq!(file_writer.write(b"ab").unwrap(), 2);
+        token.cancel();
+        assert!(matches!(
+            Error::from(file_writer.write(b"cd").unwrap_err()),
+            Error::Cancelled
+        ));
+        let writer = file_writer.finish().unwrap();
+        assert_eq!(writer.entries()[0].uncompressed_size(), 2);
+    }
+
+    #[test]
+    fn append_dir() {
+        /// Removes directory, even if test fails
+        struct TempDir(std::path::PathBuf);
+
+        impl Drop for TempDir {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_dir_all(&self.0);
+            }
+        }
+
+        let dir = TempDir(
+            std::env::temp_dir().join(format!("zip-stream-append-dir-{}", std::process::id())),
+        );
+        std::fs::create_dir_all(dir.0.join("b/c")).unwrap();
+        std::fs::write(dir.0.join("a.txt"), b"a").unwrap();
+        std::fs::write(dir.0.join("b/c/d.txt"), b"d").unwrap();
+
+        let mut writer = ZipWriter::<_, &str>::new(Cursor::new(Vec::new()));
+        writer.append_dir(&dir.0).unwrap();
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+
+        let paths = archive
+            .entries()
+            .iter()
+            .map(|e| e.path_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["a.txt", "b/", "b/c/", "b/c/d.txt"]);
+    }
+
+    #[test]
+    fn rate_limit() {
+        let mut limiter = RateLimiter::new(RateLimit::new(1024, 128));
+        let start = Instant::now();
+
+        assert_eq!(limiter.delay_at(128, start), Duration::ZERO);
+        assert_eq!(limiter.delay_at(512, start), Duration::from_millis(500));
+        // debt is paid off by waiting, idle time refills bucket up to burst only
+        let later = start + Duration::from_secs(10);
+        assert_eq!(limiter.delay_at(128, later), Duration::ZERO);
+        assert_eq!(limiter.delay_at(256, later), Duration::from_millis(250));
+
+        let limit = RateLimit::new(0, 0);
+        assert_eq!((limit.bytes_per_second(), limit.burst()), (1, 1));
+
+        let mut limiter = RateLimiter::new(RateLimit::new(1024, 128));
+        assert_eq!(limiter.available_at(start), Ok(128));
+        limiter.delay_at(128, start);
+        assert_eq!(
+            limiter.available_at(start),
+            Err(Duration::from_secs_f64(1.0 / 1024.0))
+        );
+
+        // single write doesn't exceed burst
+        let mut sink = Throttled::new(Vec::new(), RateLimit::new(1 << 30, 4));
+        assert_eq!(sink.write(b"Simple Test").unwrap(), 4);
+
+        let mut writer = ZipWriter::new_throttled(Vec::new(), RateLimit::new(1 << 30, 1 << 20));
+        assert!(matches!(
+            writer.append_data("../x", b"Simple Test"),
+            Err(Error::InvalidPath { .. })
+        ));
+        writer.append_data("test", b"Simple Test").unwrap();
+        let out = writer.finish().unwrap().into_inner();
+        assert_eq!(ZipArchive::new(Cursor::new(out)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reproducible() {
+        let data = generate_data().concat();
+        let build = |modified: i64| {
+            let mut writer = ZipWriter::new(Vec::new())
+                .extended_timestamp(true)
+                .reproducible();
+            writer
+                .start_file("deflated")
+                .modification_unix(modified)
+                .compression(DeflateConfig::best())
+                .write_all(data.as_bytes())
+                .unwrap();
+            writer
+                .start_file("stored")
+                .modification_unix(modified)
+                .write_all(data.as_bytes())
+                .unwrap();
+            writer.finish().unwrap()
+        };
+
+        let out = build(1_600_000_000);
+        assert_eq!(out, build(1_700_000_000));
+        let mut archive = ZipArchive::new(Cursor::new(out)).unwrap();
+        assert!(archive.entry(0).unwrap().compressed_size() < data.len() as u64);
+        let mut content = String::new();
+        archive
+            .by_name("deflated")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, data);
+        assert_eq!(archive.entry(0).unwrap().modification_date(), 1 << 5 | 1);
+        assert_eq!(archive.entry(0).unwrap().modification_time(), 0);
+    }
+
+    #[test]
+    fn reproducible_level() {
+        let data = generate_data().concat();
+        let build = |writer: ZipWriter<Vec<u8>, &str>, level: DeflateConfig| {
+            let mut writer = writer;
+            writer
+                .start_file("deflated")
+                .compression(level)
+                .write_all(data.as_bytes())
+                .unwrap();
+            writer.finish().unwrap()
+        };
+
+        // level is pinned, so separate writers agree, whichever level entries ask for
+        let out = build(
+            ZipWriter::new(Vec::new()).reproducible(),
+            DeflateConfig::best(),
+        );
+        let other = build(
+            ZipWriter::new(Vec::new()).reproducible(),
+            DeflateConfig::fast(),
+        );
+        assert_eq!(out, other);
+        let pinned = build(
+            ZipWriter::new(Vec::new()),
+            DeflateConfig::new(crate::compressor::deflate::REPRODUCIBLE_LEVEL),
+        );
+        assert_eq!(out, pinned);
+        assert_ne!(
+            build(ZipWriter::new(Vec::new()), DeflateConfig::best()),
+            build(ZipWriter::new(Vec::new()), DeflateConfig::fast())
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn reproducible_at() {
+        let date_time = time::Date::from_calendar_date(2021, time::Month::October, 9)
+            .unwrap()
+            .with_hms(12, 34, 56)
+            .unwrap()
+            .assume_utc();
+
+        let mut writer = ZipWriter::new(Vec::new()).reproducible_at(date_time);
+        writer
+            .start_file("test")
+            .modification(std::time::SystemTime::now())
+            .write_all(b"Simple Test")
+            .unwrap();
+        assert_eq!(
+            writer.entries()[0].modification_date_time(),
+            Some(date_time)
+        );
+
+        // dates before 1980 can't be represented
+        let mut writer =
+            ZipWriter::new(Vec::new()).reproducible_at(time::OffsetDateTime::UNIX_EPOCH);
+        writer.append_data("test", b"Simple Test").unwrap();
+        assert_eq!(writer.entries()[0].modification_date(), 1 << 5 | 1);
+    }
+
+    #[test]
+    fn time_zone() {
+        let mut writer = ZipWriter::new(Vec::new())
+            .time_zone(TimeZone::Offset(3 * 3600))
+            .extended_timestamp(true);
+        // 2021-10-09T12:34:56Z
+        writer
+            .start_file("writer")
+            .modification_unix(1_633_782_896)
+            .write_all(b"")
+            .unwrap();
+        writer
+            .start_file("entry")
+            .modification_unix(1_633_782_896)
+            .time_zone(TimeZone::Offset(-30 * 60))
+            .write_all(b"")
+            .unwrap();
+        writer.append_data("unset", b"").unwrap();
+        let archive = ZipArchive::new(Cursor::new(writer.finish().unwrap())).unwrap();
+
+        let modified = |index| {
+            let entry = archive.entry(index).unwrap();
+            crate::dos_time::format(entry.modification_time(), entry.modification_date())
+        };
+        assert_eq!(modified(0), "2021-10-09T15:34:56");
+        assert_eq!(modified(1), "2021-10-09T12:04:56");
+        assert_eq!(modified(2), "1980-01-01T00:00:00");
+
+        let mut extended = [0b1, 0, 0, 0, 0];
+        extended[1..].copy_from_slice(&1_633_782_896i32.to_le_bytes());
+        let entry = archive.entry(0).unwrap();
+        assert_eq!(
+            entry.extra_fields().find(|(id, _)| *id == 0x5455),
+            Some((0x5455, &extended[..]))
+        );
+        let entry = archive.entry(2).unwrap();
+        assert!(entry.extra_fields().all(|(id, _)| id != 0x5455));
+
+        #[cfg(feature = "chrono")]
+        {
+            use chrono::TimeZone as _;
+
+            let local = chrono::Local.timestamp_opt(1_633_782_896, 0).unwrap();
+            assert_eq!(
+                crate::dos_time::from_unix_in(1_633_782_896, TimeZone::Local),
+                crate::dos_time::from_chrono(&local)
+            );
+        }
+
+        // local offset can't be determined without time zone backend
+        #[cfg(not(any(feature = "time", feature = "chrono")))]
+        assert_eq!(
+            crate::dos_time::from_unix_in(1_633_782_896, TimeZone::Local),
+            crate::dos_time::from_unix(1_633_782_896)
+        );
+
+        // exact time would make output depend on file system
+        let mut writer = ZipWriter::new(Vec::new())
+            .extended_timestamp(true)
+            .reproducible();
+        writer
+            .start_file("test")
+            .modification_unix(1_633_782_896)
+            .write_all(b"")
+            .unwrap();
+        assert!(writer.entries()[0].extra_fields().next().is_none());
+    }
+
+    #[test]
+    fn container_mimetype() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new())).container(ContainerProfile::Epub);
+        assert!(matches!(
+            writer.append_data("mimetype", b"text/plain"),
+            Err(Error::ContainerLayout(_))
+        ));
+        writer
+            .start_file("OEBPS/content.opf")
+            .compression(DeflateConfig::default())
+            .write_all(b"<package/>")
+            .unwrap();
+        let out = writer.finish().unwrap().into_inner();
+
+        // fixed layout, which is checked by e-book readers
+        assert_eq!(&out[..4], b"PK\x03\x04");
+        assert_eq!(&out[6..10], [0; 4]); // flags and compression method
+        assert_eq!(&out[26..30], [8, 0, 0, 0]); // path and extra field lengths
+        assert_eq!(&out[30..58], b"mimetypeapplication/epub+zip");
+        // version needed to extract is the same in local header and central directory
+        let central = out.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        assert_eq!(out[4..6], out[central + 6..central + 8]);
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(out)).unwrap();
+        assert_eq!(archive.by_index(0).unwrap().name(), "mimetype");
+        let mut content = String::new();
+        archive
+            .by_name("OEBPS/content.opf")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "<package/>");
+
+        // archive without entries still gets it
+        let writer: ZipWriter<_, &str> = ZipWriter::new(Cursor::new(Vec::new())).container(
+            ContainerProfile::OpenDocument("application/vnd.oasis.opendocument.text".to_owned()),
+        );
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive.entry(0).unwrap().path(), b"mimetype");
+
+        for media_type in ["", "text/plain\r\nX: y", "tëxt/plain"] {
+            let mut writer = ZipWriter::new(Vec::new())
+                .container(ContainerProfile::OpenDocument(media_type.to_owned()));
+            assert!(matches!(
+                writer.append_data("content.xml", b""),
+                Err(Error::ContainerLayout(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn container_ooxml() {
+        let mut writer = ZipWriter::new(Vec::new()).container(ContainerProfile::Ooxml);
+        assert!(matches!(
+            writer.append_data("word/document.xml", b"<document/>"),
+            Err(Error::ContainerLayout(_))
+        ));
+        writer
+            .append_data("[Content_Types].xml", b"<Types/>")
+            .unwrap();
+        writer
+            .append_data("word/document.xml", b"<document/>")
+            .unwrap();
+        assert_eq!(writer.entries()[0].path_str(), Some("[Content_Types].xml"));
+        assert_eq!(writer.entries().len(), 2);
+    }
+
+    #[test]
+    fn container_jar() {
+        let manifest = Manifest::new()
+            .main_attribute("Main-Class", "com.example.Main")
+            .entry_attribute("com/example/", "Sealed", "true");
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()))
+            .container(ContainerProfile::Jar(manifest.clone()));
+        writer
+            .start_file("com/example/Main.class")
+            .compression(DeflateConfig::default())
+            .write_all(b"\xCA\xFE\xBA\xBE")
+            .unwrap();
+        writer.append_data("com/example/Util.class", b"").unwrap();
+        assert!(matches!(
+            writer.append_data("com/example/Main.class", b""),
+            Err(Error::DuplicatePath(_))
+        ));
+        assert!(matches!(
+            writer.append_data("META-INF/MANIFEST.MF", b""),
+            Err(Error::DuplicatePath(_))
+        ));
+        let mut archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+
+        let paths: Vec<_> = archive.entries().iter().map(|e| e.path()).collect();
+        assert_eq!(
+            paths,
+            [
+                &b"META-INF/"[..],
+                b"META-INF/MANIFEST.MF",
+                b"com/",
+                b"com/example/",
+                b"com/example/Main.class",
+                b"com/example/Util.class",
+            ]
+        );
+        assert_eq!(archive.entry(0).unwrap().extra_field(), [0xFE, 0xCA, 0, 0]);
+        assert_eq!(archive.entry(1).unwrap().flags(), 0);
+        let mut content = Vec::new();
+        archive
+            .by_name("META-INF/MANIFEST.MF")
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, manifest.to_bytes());
+
+        // synthesized directories are encoded like other paths
+        let mut writer =
+            ZipWriter::new(Vec::new()).container(ContainerProfile::Jar(Manifest::new()));
+        writer.append_data("ü/Main.class", b"").unwrap();
+        let entry = &writer.entries()[2];
+        assert_eq!(entry.path_str(), Some("ü/"));
+        assert_eq!(entry.flags() & 1 << 11, 1 << 11);
+
+        // and with the same bytes as entry path
+        let mut writer = ZipWriter::new(Vec::new())
+            .path_encoding(PathEncoding::Cp437)
+            .container(ContainerProfile::Jar(Manifest::new()));
+        writer.append_data("ü/Main.class", b"").unwrap();
+        writer.append_data("ü/Util.class", b"").unwrap();
+        let paths: Vec<_> = writer.entries().iter().map(|e| e.path_bytes()).collect();
+        assert_eq!(
+            paths[2..],
+            [&b"\x81/"[..], b"\x81/Main.class", b"\x81/Util.class"]
+        );
+
+        // rejected entry doesn't leave its directories
+        let mut writer =
+            ZipWriter::new(Vec::new()).container(ContainerProfile::Jar(Manifest::new()));
+        let long_path = format!("dir/{}", "a".repeat(70000));
+        assert!(matches!(
+            writer.append_data(&long_path, b""),
+            Err(Error::FormatLimit(FormatLimit::PathTooLong(_)))
+        ));
+        assert_eq!(writer.entries().len(), 2);
+    }
+
+    #[test]
+    fn manifest() {
+        let long_value = "a".repeat(100);
+        let manifest = Manifest::new()
+            .main_attribute("Created-By", "zip-stream")
+            .main_attribute("created-by", long_value.as_str())
+            .entry_attribute("dir/ü.class", "SHA-256-Digest", "Ünïcödé".repeat(6));
+        let text = String::from_utf8(manifest.to_bytes()).unwrap();
+
+        assert!(text.starts_with("Manifest-Version: 1.0\r\nCreated-By: aaa"));
+        assert!(text.contains("\r\n\r\nName: dir/ü.class\r\n"));
+        assert!(text.ends_with("\r\n\r\n"));
+        for line in text.split("\r\n") {
+            assert!(line.len() <= 72, "{:?}", line);
+        }
+        // continuation lines are joined back without leading space
+        let unwrapped = text.replace("\r\n ", "");
+        assert!(unwrapped.contains(&format!("Created-By: {}\r\n", long_value)));
+        assert!(unwrapped.contains(&format!("SHA-256-Digest: {}\r\n", "Ünïcödé".repeat(6))));
+        assert_eq!(manifest.validate(), Ok(()));
+
+        let long_name = "a".repeat(71);
+        for manifest in [
+            Manifest::new().main_attribute("Main Class", "Main"),
+            Manifest::new().main_attribute("Main-Clåss", "Main"),
+            Manifest::new().main_attribute(long_name.as_str(), "Main"),
+            Manifest::new().main_attribute("Main-Class", "Main\r\nX: y"),
+            Manifest::new().main_attribute("Main-Class", "Main\0"),
+            Manifest::new().entry_attribute("dir\n/", "Sealed", "true"),
+        ] {
+            let mut writer = ZipWriter::new(Vec::new()).container(ContainerProfile::Jar(manifest));
+            assert!(matches!(
+                writer.append_data("Main.class", b""),
+                Err(Error::ContainerLayout(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn dos_time() {
+        use crate::dos_time::{format, from_unix, unix_seconds};
+        use std::time::UNIX_EPOCH;
+
+        let dos = |(time, date)| format(time, date);
+        assert_eq!(dos(from_unix(1_633_782_896)), "2021-10-09T12:34:56");
+        assert_eq!(dos(from_unix(951_782_399)), "2000-02-28T23:59:58");
+        assert_eq!(dos(from_unix(951_868_800)), "2000-03-01T00:00:00");
+        assert_eq!(dos(from_unix(315_532_800)), "1980-01-01T00:00:00");
+        assert_eq!(dos(from_unix(4_354_819_199)), "2107-12-31T23:59:58");
+
+        // out of range dates are clamped
+        assert_eq!(dos(from_unix(315_532_799)), "1980-01-01T00:00:00");
+        assert_eq!(dos(from_unix(-1)), "1980-01-01T00:00:00");
+        assert_eq!(dos(from_unix(i64::MIN)), "1980-01-01T00:00:00");
+        assert_eq!(dos(from_unix(4_354_819_200)), "2107-12-31T23:59:58");
+        assert_eq!(dos(from_unix(i64::MAX)), "2107-12-31T23:59:58");
+        assert_eq!(
+            dos(from_unix(unix_seconds(UNIX_EPOCH - Duration::from_secs(1)))),
+            "1980-01-01T00:00:00"
+        );
+        assert_eq!(
+            dos(from_unix(unix_seconds(
+                UNIX_EPOCH + Duration::from_secs(1_633_782_896)
+            ))),
+            "2021-10-09T12:34:56"
+        );
+
+        let mut writer = ZipWriter::new(Vec::new());
+        writer.append_data("default", b"").unwrap();
+        assert_eq!(writer.entries()[0].modification_date(), 1 << 5 | 1);
+        assert_eq!(writer.entries()[0].modification_time(), 0);
+    }
+
+    #[test]
+    fn path_policy() {
+        let policy = PathPolicy::strict();
+        assert_eq!(&*policy.apply(b"dir/file").unwrap(), b"dir/file");
+        assert_eq!(&*policy.apply(b"dir/").unwrap(), b"dir/");
+        assert_eq!(&*policy.apply(b"/dir\\.//file").unwrap(), b"dir/file");
+        assert_eq!(&*policy.apply(b"./dir/./").unwrap(), b"dir/");
+        assert_eq!(
+            policy.apply(b"../etc/passwd"),
+            Err(PathError::ParentSegment)
+        );
+        assert_eq!(policy.apply(b"dir\\..\\.."), Err(PathError::ParentSegment));
+        assert_eq!(policy.apply(b"/./"), Err(PathError::Empty));
+        assert_eq!(policy.apply(b"file\0"), Err(PathError::Nul));
+
+        let policy = PathPolicy::verbatim();
+        assert_eq!(&*policy.apply(b"/../dir\\file").unwrap(), b"/../dir\\file");
+    }
+
+    #[test]
+    fn path_policy_writer() {
+        let mut writer = ZipWriter::new_strict(Cursor::new(Vec::new()));
+        writer.append_data("\\dir\\file", b"data").unwrap();
+        match writer.append_data("../../etc/passwd", b"data") {
+            Err(Error::InvalidPath { path, reason }) => {
+                assert_eq!(path, "../../etc/passwd");
+                assert_eq!(reason, PathError::ParentSegment);
+            }
+            e => panic!("unexpected result {:?}", e),
+        }
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive.entry(0).unwrap().path_str(), Some("dir/file"));
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.append_data("../file", b"data").unwrap();
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(archive.entry(0).unwrap().path_str(), Some("../file"));
+    }
+
+    #[test]
+    fn path_encoding() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.append_data("naïve.txt".as_bytes(), b"data").unwrap();
+        assert!(matches!(
+            writer.append_data(b"\xFF.txt", b"data"),
+            Err(Error::InvalidPath {
+                reason: PathError::NotUtf8,
+                ..
+            })
+        ));
+        let mut writer = writer.path_encoding(PathEncoding::Raw);
+        writer.append_data(b"\xFF.txt", b"data").unwrap();
+        let mut writer = writer.path_encoding(PathEncoding::Cp437);
+        writer
+            .append_data("naïve ✓.txt".as_bytes(), b"data")
+            .unwrap();
+        writer.append_data(b"plain.txt", b"data").unwrap();
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+
+        let entry = archive.entry(0).unwrap();
+        assert_eq!(entry.path(), "naïve.txt".as_bytes());
+        assert_eq!(entry.flags() & (1 << 11), 1 << 11);
+
+        let entry = archive.entry(1).unwrap();
+        assert_eq!(entry.path(), b"\xFF.txt");
+        assert_eq!(entry.flags() & (1 << 11), 0);
+
+        let entry = archive.entry(2).unwrap();
+        assert_eq!(entry.path(), b"na\x8Bve _.txt");
+        assert_eq!(entry.flags() & (1 << 11), 0);
+        let (_, unicode_path) = entry.extra_fields().find(|(id, _)| *id == 0x7075).unwrap();
+        assert_eq!(unicode_path[0], 1);
+        assert_eq!(
+            unicode_path[1..5],
+            crc32fast::hash(b"na\x8Bve _.txt").to_le_bytes()
+        );
+        assert_eq!(&unicode_path[5..], "naïve ✓.txt".as_bytes());
+
+        let entry = archive.entry(3).unwrap();
+        assert_eq!(entry.path(), b"plain.txt");
+        assert!(entry.extra_fields().all(|(id, _)| id != 0x7075));
+    }
+
+    #[test]
+    fn duplicate_policy() {
+        let mut writer = ZipWriter::new_strict(Vec::new()).duplicate_policy(DuplicatePolicy::Error);
+        writer.append_data("file", b"data").unwrap();
+        match writer.append_data("./file", b"data") {
+            Err(Error::DuplicatePath(path)) => assert_eq!(path, "file"),
+            e => panic!("unexpected result {:?}", e),
+        }
+
+        let mut writer =
+            ZipWriter::new(Cursor::new(Vec::new())).duplicate_policy(DuplicatePolicy::Rename);
+        for path in [
+            "dir/file.txt",
+            "dir/file.txt",
+            "dir/file.txt",
+            "dir/",
+            "dir/",
+        ] {
+            writer.append_data(path, b"data").unwrap();
+        }
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        let paths = archive
+            .entries()
+            .iter()
+            .map(|e| e.path_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "dir/file.txt",
+                "dir/file (1).txt",
+                "dir/file (2).txt",
+                "dir/",
+                "dir (1)/"
+            ]
+        );
+
+        // rejected entry doesn't take its path
+        let mut writer = ZipWriter::new(Vec::new()).duplicate_policy(DuplicatePolicy::Error);
+        let long_path = "a".repeat(70000);
+        for _ in 0..2 {
+            assert!(matches!(
+                writer.append_data(&long_path, b""),
+                Err(Error::FormatLimit(FormatLimit::PathTooLong(70000)))
+            ));
+        }
+
+        // paths are compared after encoding
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()))
+            .path_encoding(PathEncoding::Cp437)
+            .duplicate_policy(DuplicatePolicy::Rename);
+        writer.append_data("✓.txt", b"data").unwrap();
+        writer.append_data("✗.txt", b"data").unwrap();
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(archive.entry(0).unwrap().path(), b"_.txt");
+        assert_eq!(archive.entry(1).unwrap().path(), b"_ (1).txt");
+    }
+
     fn generate_data() -> Vec<String> {
         (0..10000).map(|n| n.to_string()).collect::<Vec<_>>()
     }
@@ -0,0 +1,42 @@
+use crate::compressor::EntryData;
+
+/// Default number of entry bytes between [Event::Progress] events
+pub(crate) const DEFAULT_PROGRESS_INTERVAL: u64 = 1024 * 1024;
+
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// Local header of entry is written
+    EntryStarted {
+        path: &'a [u8],
+    },
+    /// Sent periodically while entry data is written, see [ZipWriter::progress_interval](crate::ZipWriter::progress_interval).
+    /// `bytes_in` is entry data received so far and `bytes_out` is data written into sink
+    Progress {
+        bytes_in: u64,
+        bytes_out: u64,
+    },
+    EntryFinished {
+        path: &'a [u8],
+        data: EntryData,
+        crc32: u32,
+    },
+    /// Entry is terminated and omitted from central directory
+    EntryAborted {
+        path: &'a [u8],
+    },
+    CentralDirectoryWritten {
+        entries: u64,
+        size: u64,
+    },
+}
+
+/// Receives [Event]s of [ZipWriter](crate::ZipWriter), it's implemented for closures
+pub trait Observer {
+    fn event(&mut self, event: Event<'_>);
+}
+
+impl<F: FnMut(Event<'_>)> Observer for F {
+    fn event(&mut self, event: Event<'_>) {
+        self(event)
+    }
+}
@@ -0,0 +1,336 @@
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+
+/// Entry path, it's written into archive as raw bytes, see [PathEncoding] on how they are interpreted
+pub trait ZipPath {
+    fn as_path_bytes(&self) -> &[u8];
+}
+
+impl ZipPath for str {
+    fn as_path_bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl ZipPath for String {
+    fn as_path_bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl ZipPath for Box<str> {
+    fn as_path_bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl ZipPath for Cow<'_, str> {
+    fn as_path_bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl ZipPath for [u8] {
+    fn as_path_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+impl ZipPath for Vec<u8> {
+    fn as_path_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<T: ZipPath + ?Sized> ZipPath for &T {
+    fn as_path_bytes(&self) -> &[u8] {
+        (**self).as_path_bytes()
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::ffi::{OsStr, OsString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    use super::ZipPath;
+
+    impl ZipPath for OsStr {
+        fn as_path_bytes(&self) -> &[u8] {
+            self.as_bytes()
+        }
+    }
+
+    impl ZipPath for OsString {
+        fn as_path_bytes(&self) -> &[u8] {
+            self.as_bytes()
+        }
+    }
+
+    impl ZipPath for Path {
+        fn as_path_bytes(&self) -> &[u8] {
+            self.as_os_str().as_bytes()
+        }
+    }
+
+    impl ZipPath for PathBuf {
+        fn as_path_bytes(&self) -> &[u8] {
+            self.as_os_str().as_bytes()
+        }
+    }
+}
+
+/// How path bytes are written into archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathEncoding {
+    /// Paths must be valid UTF-8, language encoding flag (bit 11) is set for non-ASCII ones
+    #[default]
+    Utf8,
+    /// UTF-8 paths are converted into CP437 (unrepresentable characters are replaced with `_`)
+    /// and original is kept in Info-ZIP Unicode Path extra field (0x7075), so old tools
+    /// get readable names and new ones get exact. Non-UTF-8 paths are considered already encoded
+    Cp437,
+    /// Paths are written as is without language encoding flag, e.g. arbitrary Linux file names
+    Raw,
+}
+
+/// Rules applied to entry paths before they are written into archive.
+///
+/// [PathPolicy::strict] (used by [ZipWriter::new_strict](crate::ZipWriter::new_strict)) makes sure archive
+/// can't be used for zip-slip: paths are relative, use `/` as separator and never go up with `..`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPolicy {
+    normalize_separators: bool,
+    strip_leading_slash: bool,
+    remove_dot_segments: bool,
+    reject_parent_segments: bool,
+    reject_empty: bool,
+    reject_nul: bool,
+}
+
+/// Reason why path was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// Path is empty (or becomes empty after normalization)
+    Empty,
+    /// Path contains NUL character
+    Nul,
+    /// Path contains `..` segment
+    ParentSegment,
+    /// Path isn't valid UTF-8, while [PathEncoding::Utf8] is used
+    NotUtf8,
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "path is empty"),
+            PathError::Nul => write!(f, "path contains NUL character"),
+            PathError::ParentSegment => write!(f, "path contains '..' segment"),
+            PathError::NotUtf8 => write!(f, "path is not valid UTF-8"),
+        }
+    }
+}
+
+impl Default for PathPolicy {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
+
+impl PathPolicy {
+    /// Normalizes separators, strips leading `/`, removes `.` and empty segments,
+    /// rejects `..` segments, empty paths and NUL characters
+    pub fn strict() -> Self {
+        Self {
+            normalize_separators: true,
+            strip_leading_slash: true,
+            remove_dot_segments: true,
+            reject_parent_segments: true,
+            reject_empty: true,
+            reject_nul: true,
+        }
+    }
+
+    /// Writes paths as is
+    pub fn verbatim() -> Self {
+        Self {
+            normalize_separators: false,
+            strip_leading_slash: false,
+            remove_dot_segments: false,
+            reject_parent_segments: false,
+            reject_empty: false,
+            reject_nul: false,
+        }
+    }
+
+    /// Replace `\` with `/`
+    pub fn normalize_separators(mut self, value: bool) -> Self {
+        self.normalize_separators = value;
+        self
+    }
+
+    pub fn strip_leading_slash(mut self, value: bool) -> Self {
+        self.strip_leading_slash = value;
+        self
+    }
+
+    /// Remove `.` and empty segments, so `a/./b//c` becomes `a/b/c`.
+    /// Trailing `/` of directory entries is preserved
+    pub fn remove_dot_segments(mut self, value: bool) -> Self {
+        self.remove_dot_segments = value;
+        self
+    }
+
+    pub fn reject_parent_segments(mut self, value: bool) -> Self {
+        self.reject_parent_segments = value;
+        self
+    }
+
+    pub fn reject_empty(mut self, value: bool) -> Self {
+        self.reject_empty = value;
+        self
+    }
+
+    pub fn reject_nul(mut self, value: bool) -> Self {
+        self.reject_nul = value;
+        self
+    }
+
+    /// Returns normalized path, it's borrowed if nothing had to be changed
+    pub fn apply<'a>(&self, path: &'a [u8]) -> Result<Cow<'a, [u8]>, PathError> {
+        if self.reject_nul && path.contains(&0) {
+            return Err(PathError::Nul);
+        }
+
+        let mut path = Cow::Borrowed(path);
+        if self.normalize_separators && path.contains(&b'\\') {
+            path.to_mut()
+                .iter_mut()
+                .filter(|b| **b == b'\\')
+                .for_each(|b| *b = b'/');
+        }
+        if self.strip_leading_slash && path.starts_with(b"/") {
+            let start = path.iter().position(|b| *b != b'/').unwrap_or(path.len());
+            path = match path {
+                Cow::Borrowed(path) => Cow::Borrowed(&path[start..]),
+                Cow::Owned(mut path) => {
+                    path.drain(..start);
+                    Cow::Owned(path)
+                }
+            };
+        }
+        if self.remove_dot_segments && has_dot_segments(&path) {
+            path = Cow::Owned(remove_dot_segments(&path));
+        }
+
+        if self.reject_parent_segments && segments(&path).any(|segment| segment == b"..") {
+            return Err(PathError::ParentSegment);
+        }
+        if self.reject_empty && (path.is_empty() || *path == *b"/") {
+            return Err(PathError::Empty);
+        }
+
+        Ok(path)
+    }
+}
+
+fn segments(path: &[u8]) -> impl Iterator<Item = &[u8]> {
+    path.split(|b| *b == b'/')
+}
+
+/// Segments except trailing one, which is empty for directories
+fn inner_segments(path: &[u8]) -> impl Iterator<Item = &[u8]> {
+    segments(path.strip_suffix(b"/").unwrap_or(path))
+}
+
+fn has_dot_segments(path: &[u8]) -> bool {
+    let path = path.strip_prefix(b"/").unwrap_or(path);
+    !path.is_empty() && inner_segments(path).any(|segment| segment.is_empty() || segment == b".")
+}
+
+fn remove_dot_segments(path: &[u8]) -> Vec<u8> {
+    let (prefix, rest): (&[u8], _) = match path.strip_prefix(b"/") {
+        Some(rest) => (b"/", rest),
+        None => (b"", path),
+    };
+
+    let mut result = Vec::with_capacity(path.len());
+    result.extend_from_slice(prefix);
+    for segment in inner_segments(rest).filter(|s| !s.is_empty() && *s != b".") {
+        if result.len() > prefix.len() {
+            result.push(b'/');
+        }
+        result.extend_from_slice(segment);
+    }
+    if rest.ends_with(b"/") && result.len() > prefix.len() {
+        result.push(b'/');
+    }
+
+    result
+}
+
+/// What to do with entry, which path was already written into archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Write entry anyway, most extractors will use the last one
+    #[default]
+    Allow,
+    /// Return [Error::DuplicatePath](crate::Error::DuplicatePath)
+    Error,
+    /// Append ` (n)` suffix before extension of file name: `dir/file (1).txt`
+    Rename,
+}
+
+impl PathEncoding {
+    /// Path bytes, which are written into header for `path`
+    pub(crate) fn encode(self, path: &[u8]) -> Cow<'_, [u8]> {
+        match (self, std::str::from_utf8(path)) {
+            (PathEncoding::Cp437, Ok(path)) if !path.is_ascii() => Cow::Owned(encode_cp437(path)),
+            _ => Cow::Borrowed(path),
+        }
+    }
+}
+
+pub(crate) fn with_suffix(path: &[u8], n: usize) -> Vec<u8> {
+    let (path, trailing_slash): (_, &[u8]) = match path.strip_suffix(b"/") {
+        Some(path) => (path, b"/"),
+        None => (path, b""),
+    };
+    let file_name_start = path.iter().rposition(|b| *b == b'/').map_or(0, |i| i + 1);
+    let extension_start = match path[file_name_start..].iter().rposition(|b| *b == b'.') {
+        Some(0) | None => path.len(),
+        Some(i) => file_name_start + i,
+    };
+
+    let mut result = path[..extension_start].to_vec();
+    result.extend_from_slice(format!(" ({})", n).as_bytes());
+    result.extend_from_slice(&path[extension_start..]);
+    result.extend_from_slice(trailing_slash);
+    result
+}
+
+/// Characters of 0x80-0xFF CP437 codes, lower half is the same as ASCII
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}', //
+];
+
+pub(crate) fn encode_cp437(path: &str) -> Vec<u8> {
+    path.chars()
+        .map(|c| match c {
+            '\0'..='\x7F' => c as u8,
+            _ => CP437_HIGH
+                .iter()
+                .position(|high| *high == c)
+                .map_or(b'_', |i| 0x80 + i as u8),
+        })
+        .collect()
+}
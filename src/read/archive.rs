@@ -0,0 +1,479 @@
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
+use crc32fast::Hasher;
+use flate2::read::DeflateDecoder;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Take};
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDateTime, Utc};
+#[cfg(feature = "time")]
+use time::OffsetDateTime;
+
+use crate::read::invalid_data;
+
+const EOCD_SIZE: u64 = 22;
+const ZIP64_LOCATOR_SIZE: u64 = 20;
+
+/// Central directory record of an entry
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    pub(crate) path: Vec<u8>,
+    pub(crate) version_made_by: u16,
+    pub(crate) flags: u16,
+    pub(crate) compression_id: u16,
+    pub(crate) modification_time: u16,
+    pub(crate) modification_date: u16,
+    pub(crate) crc32: u32,
+    pub(crate) compressed_size: u64,
+    pub(crate) uncompressed_size: u64,
+    pub(crate) internal_attributes: u16,
+    pub(crate) external_attributes: u32,
+    pub(crate) offset: u64,
+    pub(crate) extra_field: Vec<u8>,
+    pub(crate) comment: Vec<u8>,
+}
+
+impl ZipEntry {
+    pub fn path(&self) -> &[u8] {
+        &self.path
+    }
+
+    pub fn path_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.path).ok()
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.path.ends_with(b"/")
+    }
+
+    pub fn version_made_by(&self) -> u16 {
+        self.version_made_by
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & 0x0001 != 0
+    }
+
+    pub fn compression_id(&self) -> u16 {
+        self.compression_id
+    }
+
+    pub fn modification_time(&self) -> u16 {
+        self.modification_time
+    }
+
+    pub fn modification_date(&self) -> u16 {
+        self.modification_date
+    }
+
+    #[cfg(feature = "time")]
+    pub fn modification_date_time(&self) -> Option<OffsetDateTime> {
+        crate::dos_time::to_date_time(self.modification_time, self.modification_date)
+    }
+
+    /// MS-DOS date time fields, which have no time zone, see [TimeZone](crate::TimeZone)
+    #[cfg(feature = "chrono")]
+    pub fn modification_chrono(&self) -> Option<NaiveDateTime> {
+        crate::dos_time::to_chrono(self.modification_time, self.modification_date)
+    }
+
+    /// Exact modification time from extended timestamp extra field, if entry has it
+    #[cfg(feature = "chrono")]
+    pub fn modification_utc_chrono(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.extra_fields().extended_modification()?, 0)
+    }
+
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    pub fn internal_attributes(&self) -> u16 {
+        self.internal_attributes
+    }
+
+    pub fn external_attributes(&self) -> u32 {
+        self.external_attributes
+    }
+
+    /// Offset of the local file header
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Raw extra field of central directory record
+    pub fn extra_field(&self) -> &[u8] {
+        &self.extra_field
+    }
+
+    /// Iterates over `(header id, data)` pairs of [ZipEntry::extra_field]
+    pub fn extra_fields(&self) -> ExtraFields<'_> {
+        ExtraFields(&self.extra_field)
+    }
+
+    pub fn comment(&self) -> &[u8] {
+        &self.comment
+    }
+}
+
+pub struct ExtraFields<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Iterator for ExtraFields<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut data = self.0;
+        let id = data.read_u16::<LittleEndian>().ok()?;
+        let length = data.read_u16::<LittleEndian>().ok()? as usize;
+        if length > data.len() {
+            self.0 = &[];
+            return None;
+        }
+
+        let (field, rest) = data.split_at(length);
+        self.0 = rest;
+        Some((id, field))
+    }
+}
+
+impl ExtraFields<'_> {
+    /// Modification time of extended timestamp field (0x5455) in seconds since Unix epoch
+    #[cfg(feature = "chrono")]
+    pub(crate) fn extended_modification(mut self) -> Option<i64> {
+        match self.find(|(id, _)| *id == 0x5455)? {
+            (_, [flags, mtime @ ..]) if flags & 1 != 0 && mtime.len() >= 4 => {
+                Some(LittleEndian::read_i32(mtime) as i64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// [ExtraFields] stops at malformed field silently, so records are checked before iterating
+fn check_extra_field(mut extra_field: &[u8]) -> Result<()> {
+    while extra_field.len() >= 4 {
+        let length = LittleEndian::read_u16(&extra_field[2..]) as usize;
+        if length > extra_field.len() - 4 {
+            return Err(invalid_data("extra field is out of bounds"));
+        }
+        extra_field = &extra_field[4 + length..];
+    }
+
+    Ok(())
+}
+
+/// Archive reader, which parses central directory of [R]
+/// and gives random access to the entries
+pub struct ZipArchive<R: Read + Seek> {
+    read: R,
+    entries: Vec<ZipEntry>,
+    names: HashMap<Vec<u8>, usize>,
+    comment: Vec<u8>,
+    central_directory_offset: u64,
+}
+
+impl<R: Read + Seek> ZipArchive<R> {
+    pub fn new(mut read: R) -> Result<Self> {
+        let (entries_count, central_directory_offset, comment) =
+            read_end_of_central_directory(&mut read)?;
+
+        read.seek(SeekFrom::Start(central_directory_offset))?;
+
+        let mut entries = Vec::with_capacity(entries_count.min(u16::MAX as u64) as usize);
+        for _ in 0..entries_count {
+            entries.push(read_central_directory_record(&mut read)?);
+        }
+
+        let names = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (entry.path.clone(), i))
+            .collect();
+
+        Ok(Self {
+            read,
+            entries,
+            names,
+            comment,
+            central_directory_offset,
+        })
+    }
+
+    pub fn comment(&self) -> &[u8] {
+        &self.comment
+    }
+
+    /// Offset of central directory, it's where entry data ends
+    pub fn central_directory_offset(&self) -> u64 {
+        self.central_directory_offset
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    pub fn index_of(&self, path: impl AsRef<[u8]>) -> Option<usize> {
+        self.names.get(path.as_ref()).copied()
+    }
+
+    pub fn entry(&self, index: usize) -> Option<&ZipEntry> {
+        self.entries.get(index)
+    }
+
+    pub fn entry_by_name(&self, path: impl AsRef<[u8]>) -> Option<&ZipEntry> {
+        self.index_of(path).map(|i| &self.entries[i])
+    }
+
+    /// Opens entry for reading decompressed data.
+    /// Crc is verified when the end of entry is reached
+    pub fn by_index(&mut self, index: usize) -> Result<ZipEntryReader<'_, R>> {
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "entry index out of bounds"))?;
+        if entry.is_encrypted() {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "encrypted entries are not supported",
+            ));
+        }
+        let compression_id = entry.compression_id;
+        let crc32 = entry.crc32;
+        let uncompressed_size = entry.uncompressed_size;
+
+        let data = self.raw_data(index)?;
+        let decoder = match compression_id {
+            0 => EntryDecoder::Store(data),
+            8 => EntryDecoder::Deflate(DeflateDecoder::new(data)),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "unsupported compression method",
+                ))
+            }
+        };
+
+        Ok(ZipEntryReader {
+            decoder,
+            hasher: Hasher::new(),
+            crc32,
+            uncompressed_size,
+            read: 0,
+        })
+    }
+
+    pub fn by_name(&mut self, path: impl AsRef<[u8]>) -> Result<ZipEntryReader<'_, R>> {
+        let index = self
+            .index_of(path)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "entry not found in archive"))?;
+        self.by_index(index)
+    }
+
+    /// Returns entry data as it's stored in archive (compressed and possibly encrypted)
+    pub fn raw_data(&mut self, index: usize) -> Result<Take<&mut R>> {
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "entry index out of bounds"))?;
+        let compressed_size = entry.compressed_size;
+
+        self.read.seek(SeekFrom::Start(entry.offset))?;
+        if self.read.read_u32::<LittleEndian>()? != 0x04034b50 {
+            return Err(invalid_data("invalid local file header signature"));
+        }
+        self.read.seek(SeekFrom::Current(22))?;
+        let path_length = self.read.read_u16::<LittleEndian>()?;
+        let extra_length = self.read.read_u16::<LittleEndian>()?;
+        self.read
+            .seek(SeekFrom::Current(path_length as i64 + extra_length as i64))?;
+
+        Ok((&mut self.read).take(compressed_size))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.read
+    }
+
+    pub(crate) fn into_parts(self) -> (R, Vec<ZipEntry>, Vec<u8>) {
+        (self.read, self.entries, self.comment)
+    }
+}
+
+enum EntryDecoder<'a, R: Read> {
+    Store(Take<&'a mut R>),
+    Deflate(DeflateDecoder<Take<&'a mut R>>),
+}
+
+/// Decompressed data of an entry opened by [ZipArchive::by_index]
+pub struct ZipEntryReader<'a, R: Read> {
+    decoder: EntryDecoder<'a, R>,
+    hasher: Hasher,
+    crc32: u32,
+    uncompressed_size: u64,
+    read: u64,
+}
+
+impl<'a, R: Read> Read for ZipEntryReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let count = match &mut self.decoder {
+            EntryDecoder::Store(read) => read.read(buf)?,
+            EntryDecoder::Deflate(read) => read.read(buf)?,
+        };
+        self.hasher.update(&buf[..count]);
+        self.read += count as u64;
+
+        if count == 0 && !buf.is_empty() {
+            if self.read != self.uncompressed_size {
+                return Err(invalid_data("entry size mismatch"));
+            }
+            if self.hasher.clone().finalize() != self.crc32 {
+                return Err(invalid_data("crc32 mismatch"));
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+/// Returns entries count, central directory offset and archive comment
+fn read_end_of_central_directory<R: Read + Seek>(read: &mut R) -> Result<(u64, u64, Vec<u8>)> {
+    let length = read.seek(SeekFrom::End(0))?;
+    if length < EOCD_SIZE {
+        return Err(invalid_data("archive is too small"));
+    }
+
+    // end of central directory is followed by comment up to 65535 bytes long
+    let tail_length = length.min(EOCD_SIZE + u16::MAX as u64);
+    let mut tail = vec![0; tail_length as usize];
+    read.seek(SeekFrom::Start(length - tail_length))?;
+    read.read_exact(&mut tail)?;
+
+    let eocd_position = (0..=tail.len() - EOCD_SIZE as usize)
+        .rev()
+        .find(|&i| tail[i..i + 4] == [0x50, 0x4b, 0x05, 0x06])
+        .ok_or_else(|| invalid_data("end of central directory not found"))?;
+
+    let mut eocd = &tail[eocd_position + 4..];
+    eocd.read_u16::<LittleEndian>()?; // number of this disk
+    eocd.read_u16::<LittleEndian>()?; // disk where central directory starts
+    eocd.read_u16::<LittleEndian>()?; // number of central directory records on this disk
+    let entries_count = eocd.read_u16::<LittleEndian>()?; // number of central directory records total
+    eocd.read_u32::<LittleEndian>()?; // size of the central directory
+    let central_directory_offset = eocd.read_u32::<LittleEndian>()?;
+    let comment_length = eocd.read_u16::<LittleEndian>()? as usize;
+    let comment = eocd[..comment_length.min(eocd.len())].to_vec();
+
+    let eocd_offset = length - tail_length + eocd_position as u64;
+    if eocd_offset >= ZIP64_LOCATOR_SIZE {
+        read.seek(SeekFrom::Start(eocd_offset - ZIP64_LOCATOR_SIZE))?;
+        if read.read_u32::<LittleEndian>()? == 0x07064b50 {
+            read.read_u32::<LittleEndian>()?; // disk number
+            let zip64_eocd_offset = read.read_u64::<LittleEndian>()?;
+
+            read.seek(SeekFrom::Start(zip64_eocd_offset))?;
+            if read.read_u32::<LittleEndian>()? != 0x06064b50 {
+                return Err(invalid_data(
+                    "invalid zip64 end of central directory signature",
+                ));
+            }
+            read.read_u64::<LittleEndian>()?; // size of the record
+            read.read_u16::<LittleEndian>()?; // version made by
+            read.read_u16::<LittleEndian>()?; // version to extract
+            read.read_u32::<LittleEndian>()?; // number of this disk
+            read.read_u32::<LittleEndian>()?; // disk where central directory starts
+            read.read_u64::<LittleEndian>()?; // number of central directory records on this disk
+            let entries_count = read.read_u64::<LittleEndian>()?;
+            read.read_u64::<LittleEndian>()?; // size of the central directory
+            let central_directory_offset = read.read_u64::<LittleEndian>()?;
+
+            return Ok((entries_count, central_directory_offset, comment));
+        }
+    }
+
+    Ok((
+        entries_count as u64,
+        central_directory_offset as u64,
+        comment,
+    ))
+}
+
+fn read_central_directory_record<R: Read>(read: &mut R) -> Result<ZipEntry> {
+    if read.read_u32::<LittleEndian>()? != 0x02014b50 {
+        return Err(invalid_data("invalid central directory record signature"));
+    }
+    let version_made_by = read.read_u16::<LittleEndian>()?;
+    read.read_u16::<LittleEndian>()?; // version to extract
+    let flags = read.read_u16::<LittleEndian>()?;
+    let compression_id = read.read_u16::<LittleEndian>()?;
+    let modification_time = read.read_u16::<LittleEndian>()?;
+    let modification_date = read.read_u16::<LittleEndian>()?;
+    let crc32 = read.read_u32::<LittleEndian>()?;
+    let mut compressed_size = read.read_u32::<LittleEndian>()? as u64;
+    let mut uncompressed_size = read.read_u32::<LittleEndian>()? as u64;
+    let path_length = read.read_u16::<LittleEndian>()?;
+    let extra_length = read.read_u16::<LittleEndian>()?;
+    let comment_length = read.read_u16::<LittleEndian>()?;
+    read.read_u16::<LittleEndian>()?; // disk number start
+    let internal_attributes = read.read_u16::<LittleEndian>()?;
+    let external_attributes = read.read_u32::<LittleEndian>()?;
+    let mut offset = read.read_u32::<LittleEndian>()? as u64;
+
+    let mut path = vec![0; path_length as usize];
+    read.read_exact(&mut path)?;
+    let mut extra_field = vec![0; extra_length as usize];
+    read.read_exact(&mut extra_field)?;
+    let mut comment = vec![0; comment_length as usize];
+    read.read_exact(&mut comment)?;
+
+    check_extra_field(&extra_field)?;
+    for (id, mut data) in ExtraFields(&extra_field) {
+        if id == 0x0001 {
+            // ZIP64 extended information, only overflowed fields are present
+            if uncompressed_size == 0xFFFFFFFF {
+                uncompressed_size = data.read_u64::<LittleEndian>()?;
+            }
+            if compressed_size == 0xFFFFFFFF {
+                compressed_size = data.read_u64::<LittleEndian>()?;
+            }
+            if offset == 0xFFFFFFFF {
+                offset = data.read_u64::<LittleEndian>()?;
+            }
+        }
+    }
+
+    Ok(ZipEntry {
+        path,
+        version_made_by,
+        flags,
+        compression_id,
+        modification_time,
+        modification_date,
+        crc32,
+        compressed_size,
+        uncompressed_size,
+        internal_attributes,
+        external_attributes,
+        offset,
+        extra_field,
+        comment,
+    })
+}
@@ -0,0 +1,11 @@
+pub use archive::{ExtraFields, ZipArchive, ZipEntry, ZipEntryReader};
+pub use stream::{LocalHeader, ZipStreamEntry, ZipStreamReader};
+
+mod archive;
+mod stream;
+
+use std::io::{Error, ErrorKind};
+
+pub(crate) fn invalid_data(message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,475 @@
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
+use crc32fast::Hasher;
+use flate2::{Decompress, FlushDecompress, Status};
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::read::invalid_data;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+const DATA_DESCRIPTOR_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x07, 0x08];
+
+/// Forward-only archive reader, which walks over local file headers
+/// and doesn't need [std::io::Seek], so archive can be read directly from pipe or socket.
+///
+/// Stored entries with unknown size (general purpose bit 3) are delimited
+/// by searching for data descriptor, which must have a signature in this case.
+pub struct ZipStreamReader<R: Read> {
+    read: Lookahead<R>,
+    entry: Option<EntryState>,
+    finished: bool,
+}
+
+/// Local header of the entry, which is currently read by [ZipStreamReader]
+#[derive(Debug, Clone)]
+pub struct LocalHeader {
+    path: Vec<u8>,
+    flags: u16,
+    compression_id: u16,
+    modification_time: u16,
+    modification_date: u16,
+    crc32: u32,
+    compressed_size: Option<u64>,
+    uncompressed_size: Option<u64>,
+    /// Local header has ZIP64 extended information, so data descriptor has 64-bit sizes
+    zip64: bool,
+}
+
+impl LocalHeader {
+    pub fn path(&self) -> &[u8] {
+        &self.path
+    }
+
+    pub fn path_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.path).ok()
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    pub fn compression_id(&self) -> u16 {
+        self.compression_id
+    }
+
+    pub fn modification_time(&self) -> u16 {
+        self.modification_time
+    }
+
+    pub fn modification_date(&self) -> u16 {
+        self.modification_date
+    }
+
+    /// Crc and sizes are unknown until entry is read, if they are stored in data descriptor
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    pub fn compressed_size(&self) -> Option<u64> {
+        self.compressed_size
+    }
+
+    pub fn uncompressed_size(&self) -> Option<u64> {
+        self.uncompressed_size
+    }
+
+    fn has_data_descriptor(&self) -> bool {
+        self.flags & 0x0008 != 0
+    }
+}
+
+struct EntryState {
+    header: LocalHeader,
+    decoder: Decoder,
+    hasher: Hasher,
+    compressed: u64,
+    uncompressed: u64,
+    done: bool,
+}
+
+enum Decoder {
+    Store,
+    Deflate(Box<Decompress>),
+}
+
+/// Decompressed data of the current entry. Crc and sizes are verified when the end is reached.
+pub struct ZipStreamEntry<'a, R: Read> {
+    reader: &'a mut ZipStreamReader<R>,
+}
+
+impl<R: Read> ZipStreamReader<R> {
+    pub fn new(read: R) -> Self {
+        Self {
+            read: Lookahead::new(read),
+            entry: None,
+            finished: false,
+        }
+    }
+
+    /// Skips rest of the current entry and returns the next one,
+    /// or [None] when central directory is reached
+    pub fn next_entry(&mut self) -> Result<Option<ZipStreamEntry<'_, R>>> {
+        if self.entry.is_some() {
+            std::io::copy(&mut ZipStreamEntry { reader: self }, &mut std::io::sink())?;
+            self.entry = None;
+        }
+        if self.finished {
+            return Ok(None);
+        }
+
+        let signature = self.read.fill_to(4)?;
+        if signature.is_empty() {
+            self.finished = true;
+            return Ok(None);
+        }
+        if signature.len() < 4 {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+        match LittleEndian::read_u32(signature) {
+            0x04034b50 => {}
+            // central directory or end of central directory records (empty archive)
+            0x02014b50 | 0x06064b50 | 0x06054b50 => {
+                self.finished = true;
+                return Ok(None);
+            }
+            _ => return Err(invalid_data("invalid local file header signature")),
+        }
+        self.read.consume(4);
+
+        let header = read_local_header(&mut self.read)?;
+        if header.flags & 0x0001 != 0 {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "encrypted entries are not supported",
+            ));
+        }
+        let decoder = match header.compression_id {
+            0 => Decoder::Store,
+            8 => Decoder::Deflate(Box::new(Decompress::new(false))),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "unsupported compression method",
+                ))
+            }
+        };
+        if header.compression_id == 0 && header.compressed_size != header.uncompressed_size {
+            return Err(invalid_data("stored entry sizes mismatch"));
+        }
+
+        self.entry = Some(EntryState {
+            header,
+            decoder,
+            hasher: Hasher::new(),
+            compressed: 0,
+            uncompressed: 0,
+            done: false,
+        });
+
+        Ok(Some(ZipStreamEntry { reader: self }))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.read.read
+    }
+
+    fn read_entry(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let entry = match &mut self.entry {
+            Some(entry) if !entry.done => entry,
+            _ => return Ok(0),
+        };
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let (read, end) = match &mut entry.decoder {
+            Decoder::Store => match entry.header.compressed_size {
+                Some(size) => read_stored(&mut self.read, buf, size - entry.compressed)?,
+                None => read_stored_until_descriptor(&mut self.read, buf, entry)?,
+            },
+            Decoder::Deflate(decompress) => read_deflated(&mut self.read, buf, decompress)?,
+        };
+
+        entry.hasher.update(&buf[..read]);
+        entry.uncompressed += read as u64;
+        match &entry.decoder {
+            Decoder::Store => entry.compressed += read as u64,
+            Decoder::Deflate(decompress) => entry.compressed = decompress.total_in(),
+        }
+
+        if end {
+            entry.done = true;
+            finish_entry(&mut self.read, entry)?;
+        }
+
+        Ok(read)
+    }
+}
+
+impl<'a, R: Read> ZipStreamEntry<'a, R> {
+    pub fn header(&self) -> &LocalHeader {
+        &self.reader.entry.as_ref().unwrap().header
+    }
+}
+
+impl<'a, R: Read> Read for ZipStreamEntry<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.reader.read_entry(buf)
+    }
+}
+
+fn read_local_header<R: Read>(read: &mut Lookahead<R>) -> Result<LocalHeader> {
+    read.read_u16::<LittleEndian>()?; // version to extract
+    let flags = read.read_u16::<LittleEndian>()?;
+    let compression_id = read.read_u16::<LittleEndian>()?;
+    let modification_time = read.read_u16::<LittleEndian>()?;
+    let modification_date = read.read_u16::<LittleEndian>()?;
+    let crc32 = read.read_u32::<LittleEndian>()?;
+    let mut compressed_size = read.read_u32::<LittleEndian>()? as u64;
+    let mut uncompressed_size = read.read_u32::<LittleEndian>()? as u64;
+    let path_length = read.read_u16::<LittleEndian>()?;
+    let extra_length = read.read_u16::<LittleEndian>()?;
+
+    let mut path = vec![0; path_length as usize];
+    read.read_exact(&mut path)?;
+    let mut extra = vec![0; extra_length as usize];
+    read.read_exact(&mut extra)?;
+
+    let mut zip64 = false;
+    let mut extra = &extra[..];
+    while extra.len() >= 4 {
+        let id = extra.read_u16::<LittleEndian>()?;
+        let length = extra.read_u16::<LittleEndian>()? as usize;
+        if length > extra.len() {
+            return Err(invalid_data("extra field is out of bounds"));
+        }
+        let (mut data, rest) = extra.split_at(length);
+        extra = rest;
+
+        // local ZIP64 extended information contains both sizes
+        if id == 0x0001 {
+            zip64 = true;
+            if uncompressed_size == 0xFFFFFFFF || compressed_size == 0xFFFFFFFF {
+                uncompressed_size = data.read_u64::<LittleEndian>()?;
+                compressed_size = data.read_u64::<LittleEndian>()?;
+            }
+        }
+    }
+
+    let mut header = LocalHeader {
+        path,
+        flags,
+        compression_id,
+        modification_time,
+        modification_date,
+        crc32,
+        compressed_size: Some(compressed_size),
+        uncompressed_size: Some(uncompressed_size),
+        zip64,
+    };
+    if header.has_data_descriptor() {
+        header.compressed_size = None;
+        header.uncompressed_size = None;
+    }
+
+    Ok(header)
+}
+
+fn read_stored<R: Read>(
+    read: &mut Lookahead<R>,
+    buf: &mut [u8],
+    remaining: u64,
+) -> Result<(usize, bool)> {
+    if remaining == 0 {
+        return Ok((0, true));
+    }
+
+    let available = read.fill_to(1)?;
+    if available.is_empty() {
+        return Err(Error::from(ErrorKind::UnexpectedEof));
+    }
+    let count = available
+        .len()
+        .min(buf.len())
+        .min(remaining.min(usize::MAX as u64) as usize);
+    buf[..count].copy_from_slice(&available[..count]);
+    read.consume(count);
+
+    Ok((count, count as u64 == remaining))
+}
+
+/// Copies data up to the data descriptor, which crc and size match data read so far
+fn read_stored_until_descriptor<R: Read>(
+    read: &mut Lookahead<R>,
+    buf: &mut [u8],
+    entry: &EntryState,
+) -> Result<(usize, bool)> {
+    // signature, crc and two 64-bit sizes
+    let available = read.fill_to(24)?;
+    let candidate = available
+        .windows(4)
+        .position(|window| window == DATA_DESCRIPTOR_SIGNATURE);
+
+    let count = match candidate {
+        Some(0) => {
+            if is_stored_descriptor(available, entry) {
+                return Ok((0, true));
+            }
+            // it's just a data looking like signature
+            1
+        }
+        Some(position) => position,
+        // last bytes may be the beginning of a signature
+        None if available.len() > 3 => available.len() - 3,
+        None => return Err(Error::from(ErrorKind::UnexpectedEof)),
+    };
+
+    let count = count.min(buf.len());
+    buf[..count].copy_from_slice(&available[..count]);
+    read.consume(count);
+
+    Ok((count, false))
+}
+
+fn is_stored_descriptor(descriptor: &[u8], entry: &EntryState) -> bool {
+    if descriptor.len() < 16
+        || LittleEndian::read_u32(&descriptor[4..]) != entry.hasher.clone().finalize()
+    {
+        return false;
+    }
+
+    let size = entry.uncompressed;
+    (LittleEndian::read_u32(&descriptor[8..]) as u64 == size
+        && LittleEndian::read_u32(&descriptor[12..]) as u64 == size)
+        || (descriptor.len() >= 24
+            && LittleEndian::read_u64(&descriptor[8..]) == size
+            && LittleEndian::read_u64(&descriptor[16..]) == size)
+}
+
+fn read_deflated<R: Read>(
+    read: &mut Lookahead<R>,
+    buf: &mut [u8],
+    decompress: &mut Decompress,
+) -> Result<(usize, bool)> {
+    loop {
+        let input = read.fill_to(1)?;
+        let eof = input.is_empty();
+
+        let total_in = decompress.total_in();
+        let total_out = decompress.total_out();
+        let status = decompress
+            .decompress(input, buf, FlushDecompress::None)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        read.consume((decompress.total_in() - total_in) as usize);
+        let count = (decompress.total_out() - total_out) as usize;
+
+        match status {
+            Status::StreamEnd => return Ok((count, true)),
+            _ if count > 0 => return Ok((count, false)),
+            _ if eof => return Err(Error::from(ErrorKind::UnexpectedEof)),
+            _ => {}
+        }
+    }
+}
+
+fn finish_entry<R: Read>(read: &mut Lookahead<R>, entry: &mut EntryState) -> Result<()> {
+    let compressed = entry.compressed;
+    let uncompressed = entry.uncompressed;
+    let header = &mut entry.header;
+
+    if header.has_data_descriptor() {
+        // signature is optional, but we always write it
+        if read.fill_to(4)?.starts_with(&DATA_DESCRIPTOR_SIGNATURE) {
+            read.consume(4);
+        }
+
+        let descriptor = read.fill_to(20)?;
+        if descriptor.len() < 12 {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+        header.crc32 = LittleEndian::read_u32(descriptor);
+
+        // sizes are 64-bit, if local header has ZIP64 extended information. Some writers
+        // put 32-bit ones there for small entries anyway, so they are accepted too,
+        // but only after 64-bit ones: zeros of empty entry match both variants
+        let sizes_64 = descriptor.len() >= 20
+            && LittleEndian::read_u64(&descriptor[4..]) == compressed
+            && LittleEndian::read_u64(&descriptor[12..]) == uncompressed;
+        let sizes_32 = LittleEndian::read_u32(&descriptor[4..]) as u64 == compressed
+            && LittleEndian::read_u32(&descriptor[8..]) as u64 == uncompressed;
+        let length = if header.zip64 && sizes_64 {
+            20
+        } else if sizes_32 {
+            12
+        } else {
+            return Err(invalid_data("data descriptor sizes mismatch"));
+        };
+        read.consume(length);
+
+        header.compressed_size = Some(compressed);
+        header.uncompressed_size = Some(uncompressed);
+    } else if header.compressed_size != Some(compressed)
+        || header.uncompressed_size != Some(uncompressed)
+    {
+        return Err(invalid_data("entry sizes mismatch"));
+    }
+
+    if header.crc32 != entry.hasher.clone().finalize() {
+        return Err(invalid_data("crc32 mismatch"));
+    }
+
+    Ok(())
+}
+
+/// Buffered reader, which can guarantee minimal amount of bytes available for lookahead
+struct Lookahead<R: Read> {
+    read: R,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+}
+
+impl<R: Read> Lookahead<R> {
+    fn new(read: R) -> Self {
+        Self {
+            read,
+            buf: vec![0; BUFFER_SIZE],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Returns at least `n` bytes, unless end of stream is reached
+    fn fill_to(&mut self, n: usize) -> Result<&[u8]> {
+        if self.end - self.start < n {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+
+            while self.end < n {
+                match self.read.read(&mut self.buf[self.end..]) {
+                    Ok(0) => break,
+                    Ok(count) => self.end += count,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(&self.buf[self.start..self.end])
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.start += n;
+    }
+}
+
+impl<R: Read> Read for Lookahead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let available = self.fill_to(1)?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs::File;
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
+
+use crate::error::Result;
+use crate::{ZipPath, ZipWriter};
+
+/// Sink, which can be cut to given length
+pub trait Truncate {
+    fn truncate(&mut self, len: u64) -> io::Result<()>;
+}
+
+impl Truncate for File {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.set_len(len)
+    }
+}
+
+impl Truncate for Cursor<Vec<u8>> {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.get_mut().truncate(len as usize);
+        Ok(())
+    }
+}
+
+impl Truncate for Cursor<&mut Vec<u8>> {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.get_mut().truncate(len as usize);
+        Ok(())
+    }
+}
+
+impl<T: Truncate + ?Sized> Truncate for &mut T {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        (**self).truncate(len)
+    }
+}
+
+/// What was kept by [ZipWriter::recover]
+#[derive(Debug, Clone)]
+pub struct RecoveryReport {
+    entries: Vec<Vec<u8>>,
+    committed_position: u64,
+    discarded: u64,
+}
+
+impl RecoveryReport {
+    /// Paths of entries, which survived and are listed in central directory
+    pub fn entries(&self) -> impl Iterator<Item = &[u8]> {
+        self.entries.iter().map(|path| &path[..])
+    }
+
+    /// Length of kept entry data, central directory is written right after it
+    pub fn committed_position(&self) -> u64 {
+        self.committed_position
+    }
+
+    /// Number of bytes of unfinished entry, which were cut off
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+}
+
+impl<W: Write + Seek + Truncate, P: ZipPath> ZipWriter<W, P> {
+    /// Makes valid archive out of writer, which failed in the middle of an entry
+    /// (e.g. after sink error): everything after the last complete entry is truncated
+    /// and central directory of complete entries is written.
+    /// Archive is expected to start at the beginning of the sink
+    pub fn recover(mut self) -> Result<(W, RecoveryReport)> {
+        let end = self.write.seek(SeekFrom::End(0))?;
+        self.write.truncate(self.committed_position)?;
+        self.write.seek(SeekFrom::Start(self.committed_position))?;
+        self.position = self.committed_position;
+
+        let report = RecoveryReport {
+            entries: self
+                .entries
+                .iter()
+                .map(|header| header.path_bytes().to_owned())
+                .collect(),
+            committed_position: self.committed_position,
+            discarded: end.saturating_sub(self.committed_position),
+        };
+
+        Ok((self.write_central_directory()?, report))
+    }
+}
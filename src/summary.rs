@@ -0,0 +1,108 @@
+use std::io::{self, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::{dos_time, Header, ZipPath, ZipWriter};
+
+/// Entries of archive, see [ZipWriter::summary]. With `serde` feature it can be serialized,
+/// e.g. as JSON, entries can be written as CSV with [Summary::write_csv]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Summary {
+    pub entries: Vec<EntrySummary>,
+    pub total_compressed_size: u64,
+    pub total_uncompressed_size: u64,
+    /// Offset of central directory, which is the size of entries data
+    pub central_directory_offset: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EntrySummary {
+    /// Path as written into archive, non-UTF-8 bytes are replaced
+    pub path: String,
+    pub offset: u64,
+    pub compression_id: u16,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub crc32: u32,
+    pub modification_time: u16,
+    pub modification_date: u16,
+    /// Modification date time as `YYYY-MM-DDTHH:MM:SS`
+    pub modified: String,
+}
+
+impl Summary {
+    /// Writes entries as CSV (RFC 4180) with header row, columns are named
+    /// and ordered like [EntrySummary] fields
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        out.write_all(b"path,offset,compression_id,compressed_size,uncompressed_size,crc32,modification_time,modification_date,modified\r\n")?;
+        for entry in &self.entries {
+            write!(
+                out,
+                "{},{},{},{},{},{},{},{},{}\r\n",
+                csv_field(&entry.path),
+                entry.offset,
+                entry.compression_id,
+                entry.compressed_size,
+                entry.uncompressed_size,
+                entry.crc32,
+                entry.modification_time,
+                entry.modification_date,
+                entry.modified
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Quotes field, if it contains separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\r', '\n'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+impl<P: ZipPath> From<&Header<P>> for EntrySummary {
+    fn from(header: &Header<P>) -> Self {
+        Self {
+            path: header.path_lossy(),
+            offset: header.offset,
+            compression_id: header.compression_id,
+            compressed_size: header.compressed_size,
+            uncompressed_size: header.uncompressed_size,
+            crc32: header.crc32,
+            modification_time: header.modification_time,
+            modification_date: header.modification_date,
+            modified: dos_time::format(header.modification_time, header.modification_date),
+        }
+    }
+}
+
+impl<W: Write, P: ZipPath> ZipWriter<W, P> {
+    /// Summary of entries written so far
+    pub fn summary(&self) -> Summary {
+        let entries: Vec<EntrySummary> = self.entries.iter().map(EntrySummary::from).collect();
+
+        Summary {
+            total_compressed_size: entries.iter().map(|e| e.compressed_size).sum(),
+            total_uncompressed_size: entries.iter().map(|e| e.uncompressed_size).sum(),
+            central_directory_offset: self.committed_position,
+            entries,
+        }
+    }
+
+    /// Same as [ZipWriter::finish], but also returns [Summary] of all entries
+    pub fn finish_with_summary(mut self) -> Result<(W, Summary)> {
+        // leading entries of container are written on finish, if there are no other entries
+        self.start_container()?;
+        let summary = self.summary();
+
+        Ok((self.finish()?, summary))
+    }
+}
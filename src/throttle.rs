@@ -0,0 +1,152 @@
+use std::io::{self, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
+
+use crate::{Truncate, ZipPath, ZipWriter};
+
+/// Token bucket parameters: sink is written with `bytes_per_second` on average,
+/// up to `burst` bytes can be written at once after idle time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    bytes_per_second: u64,
+    burst: u64,
+}
+
+impl RateLimit {
+    /// Both values are at least 1, so writing always makes progress
+    pub fn new(bytes_per_second: u64, burst: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            burst: burst.max(1),
+        }
+    }
+
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+
+    pub fn burst(&self) -> u64 {
+        self.burst
+    }
+}
+
+/// Runtime agnostic token bucket, it only computes delays.
+/// [Throttled] sleeps them for blocking sinks, for async sinks sleep
+/// returned delay with your runtime after each write
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    /// Negative, when more than available was consumed and caller has to wait
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            updated_at: Instant::now(),
+        }
+    }
+
+    /// Consumes `bytes` and returns how long to wait before writing more
+    pub fn delay(&mut self, bytes: usize) -> Duration {
+        self.delay_at(bytes, Instant::now())
+    }
+
+    pub(crate) fn delay_at(&mut self, bytes: usize, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= bytes as f64;
+
+        if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / self.limit.bytes_per_second as f64)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Number of bytes, which can be written at `now`, or delay until at least one byte can be
+    pub(crate) fn available_at(&mut self, now: Instant) -> Result<usize, Duration> {
+        self.refill(now);
+
+        if self.tokens >= 1.0 {
+            Ok(self.tokens as usize)
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.limit.bytes_per_second as f64,
+            ))
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.updated_at = now;
+        self.tokens = (self.tokens + elapsed * self.limit.bytes_per_second as f64)
+            .min(self.limit.burst as f64);
+    }
+}
+
+/// Blocking sink with limited write rate, single write is cut to bytes available in bucket
+pub struct Throttled<W: Write> {
+    inner: W,
+    limiter: RateLimiter,
+}
+
+impl<W: Write> Throttled<W> {
+    pub fn new(inner: W, limit: RateLimit) -> Self {
+        Self {
+            inner,
+            limiter: RateLimiter::new(limit),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for Throttled<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return self.inner.write(buf);
+        }
+
+        let available = loop {
+            match self.limiter.available_at(Instant::now()) {
+                Ok(available) => break available,
+                Err(delay) => std::thread::sleep(delay),
+            }
+        };
+        let count = self.inner.write(&buf[..buf.len().min(available)])?;
+        self.limiter.tokens -= count as f64;
+
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write + Seek> Seek for Throttled<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+impl<W: Write + Truncate> Truncate for Throttled<W> {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.inner.truncate(len)
+    }
+}
+
+impl<W: Write, P: ZipPath> ZipWriter<Throttled<W>, P> {
+    /// Same as [ZipWriter::new_strict], but archive is written with limited rate
+    pub fn new_throttled(write: W, limit: RateLimit) -> Self {
+        Self::new_strict(Throttled::new(write, limit))
+    }
+}
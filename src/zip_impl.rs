@@ -1,12 +1,41 @@
 use byteorder::{LittleEndian, WriteBytesExt};
-use std::cmp::min;
-use std::io::Result;
-use std::io::Write;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::borrow::Cow;
+use std::cmp::{max, min};
+use std::collections::HashSet;
+use std::io::{self, Write};
+#[cfg(feature = "time")]
+use time::OffsetDateTime;
+
+use crate::cancellation::CancellationToken;
+use crate::compressor::EntryData;
+use crate::container::ContainerProfile;
+#[cfg(any(feature = "time", feature = "chrono"))]
+use crate::dos_time;
+use crate::dos_time::TimeZone;
+use crate::error::{Error, FormatLimit, Result};
+use crate::observer::{Event, Observer};
+use crate::path::{
+    encode_cp437, with_suffix, DuplicatePolicy, PathEncoding, PathError, PathPolicy, ZipPath,
+};
+use crate::read::ExtraFields;
+
+/// ZIP64 extended information with both sizes and offset, which is the longest variant we write
+const MAX_ZIP64_EXTRA_LENGTH: usize = 28;
+
+/// Path is replaced with owned one only if path policies or encoding had to change it
+#[derive(Debug)]
+pub(crate) enum HeaderPath<P: ZipPath> {
+    Original(P),
+    Owned(Vec<u8>),
+}
 
 #[derive(Debug)]
-pub struct Header<P: AsRef<str>> {
+pub struct Header<P: ZipPath> {
     pub(crate) compression_id: u16,
-    pub(crate) path: P,
+    pub(crate) flags: u16,
+    pub(crate) path: HeaderPath<P>,
     pub(crate) modification_time: u16,
     pub(crate) modification_date: u16,
 
@@ -14,38 +43,239 @@ pub struct Header<P: AsRef<str>> {
     pub(crate) uncompressed_size: u64,
     pub(crate) crc32: u32,
     pub(crate) offset: u64,
+
+    /// Extra fields written after ZIP64 extended information in both local and central headers
+    pub(crate) extra_field: Vec<u8>,
+    /// Fields of central directory only, which are kept for entries of appended archive.
+    /// Version needed to extract is written as version made by, if it's not set
+    pub(crate) version_made_by: Option<u16>,
+    pub(crate) internal_attributes: u16,
+    pub(crate) external_attributes: u32,
+    pub(crate) comment: Vec<u8>,
+    /// Aborted entry is terminated with data descriptor, but omitted from central directory
+    pub(crate) aborted: bool,
 }
-impl<P: AsRef<str>> Header<P> {
-    fn path_str(&self) -> &str {
-        self.path.as_ref()
+impl<P: ZipPath> Header<P> {
+    /// Path as written into archive, path policies and encoding are applied when entry is started
+    pub fn path(&self) -> &[u8] {
+        self.path_bytes()
+    }
+
+    pub fn path_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.path_bytes()).ok()
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.path_bytes().ends_with(b"/")
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & 0x0001 != 0
+    }
+
+    pub fn compression_id(&self) -> u16 {
+        self.compression_id
+    }
+
+    pub fn modification_time(&self) -> u16 {
+        self.modification_time
+    }
+
+    pub fn modification_date(&self) -> u16 {
+        self.modification_date
+    }
+
+    #[cfg(feature = "time")]
+    pub fn modification_date_time(&self) -> Option<OffsetDateTime> {
+        dos_time::to_date_time(self.modification_time, self.modification_date)
+    }
+
+    /// MS-DOS date time fields, which have no time zone, see [TimeZone](crate::TimeZone)
+    #[cfg(feature = "chrono")]
+    pub fn modification_chrono(&self) -> Option<NaiveDateTime> {
+        dos_time::to_chrono(self.modification_time, self.modification_date)
+    }
+
+    /// Exact modification time from extended timestamp extra field, if entry has it
+    #[cfg(feature = "chrono")]
+    pub fn modification_utc_chrono(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.extra_fields().extended_modification()?, 0)
+    }
+
+    /// Zero until entry is finished, it's also zero for AE-2 encrypted entries
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    /// Zero until entry is finished
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// Zero until entry is finished
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// Offset of local header in archive, it's set when entry is started
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Extra fields except ZIP64 extended information, which is generated on write
+    pub fn extra_field(&self) -> &[u8] {
+        &self.extra_field
+    }
+
+    pub fn extra_fields(&self) -> ExtraFields<'_> {
+        ExtraFields(&self.extra_field)
+    }
+
+    /// Version needed to extract: 4.5 for ZIP64, 5.1 for WinZip AES (method 99)
+    pub(crate) fn version_needed(&self) -> u16 {
+        if self.compression_id == 99 {
+            0x33
+        } else {
+            0x2D
+        }
+    }
+
+    pub(crate) fn path_bytes(&self) -> &[u8] {
+        match &self.path {
+            HeaderPath::Original(path) => path.as_path_bytes(),
+            HeaderPath::Owned(path) => path,
+        }
+    }
+
+    /// Path for error messages
+    pub(crate) fn path_lossy(&self) -> String {
+        String::from_utf8_lossy(self.path_bytes()).into_owned()
+    }
+
+    fn invalid_path(&self, reason: PathError) -> Error {
+        Error::InvalidPath {
+            path: self.path_lossy(),
+            reason,
+        }
+    }
+
+    fn apply_path_policy(&mut self, policy: &PathPolicy) -> Result<()> {
+        match policy.apply(self.path_bytes()) {
+            Ok(Cow::Borrowed(_)) => Ok(()),
+            Ok(Cow::Owned(path)) => {
+                self.path = HeaderPath::Owned(path);
+                Ok(())
+            }
+            Err(reason) => Err(self.invalid_path(reason)),
+        }
+    }
+
+    pub(crate) fn apply_path_encoding(&mut self, encoding: PathEncoding) -> Result<()> {
+        let path = match (encoding, std::str::from_utf8(self.path_bytes())) {
+            (_, Ok(path)) if path.is_ascii() => return Ok(()),
+            (PathEncoding::Utf8, Ok(_)) => {
+                self.flags |= 1 << 11; // language encoding flag
+                return Ok(());
+            }
+            (PathEncoding::Utf8, Err(_)) => return Err(self.invalid_path(PathError::NotUtf8)),
+            (PathEncoding::Cp437, Ok(path)) => path.to_owned(),
+            (PathEncoding::Cp437, Err(_)) | (PathEncoding::Raw, _) => return Ok(()),
+        };
+
+        let encoded = encode_cp437(&path);
+        // Info-ZIP Unicode Path: version, crc32 of header path, UTF-8 path
+        let mut unicode_path = Vec::with_capacity(5 + path.len());
+        unicode_path.push(1);
+        unicode_path.extend_from_slice(&crc32fast::hash(&encoded).to_le_bytes());
+        unicode_path.extend_from_slice(path.as_bytes());
+        self.add_extra_field(0x7075, &unicode_path);
+        self.path = HeaderPath::Owned(encoded);
+
+        Ok(())
     }
 
-    fn path_bytes(&self) -> &[u8] {
-        self.path_str().as_bytes()
+    pub(crate) fn add_extra_field(&mut self, id: u16, data: &[u8]) {
+        self.extra_field.extend_from_slice(&id.to_le_bytes());
+        self.extra_field
+            .extend_from_slice(&(data.len() as u16).to_le_bytes());
+        self.extra_field.extend_from_slice(data);
     }
 }
 
-pub struct ZipWriter<W: Write, P: AsRef<str>> {
+pub struct ZipWriter<W: Write, P: ZipPath> {
     pub(crate) write: W,
     pub(crate) position: u64,
+    /// Position right after the last complete entry
+    pub(crate) committed_position: u64,
     pub(crate) entries: Vec<Header<P>>,
+    pub(crate) path_policy: PathPolicy,
+    pub(crate) duplicate_policy: DuplicatePolicy,
+    /// Paths of written entries, maintained only if duplicates aren't allowed
+    pub(crate) paths: Option<HashSet<Vec<u8>>>,
+    pub(crate) path_encoding: PathEncoding,
+    pub(crate) observer: Option<Box<dyn Observer + Send>>,
+    pub(crate) progress_interval: u64,
+    /// `bytes_in` of current entry, at which the next progress event is sent
+    pub(crate) next_progress: u64,
+    /// Bytes of current entry data written into sink
+    pub(crate) entry_bytes_out: u64,
+    pub(crate) cancellation: Option<CancellationToken>,
+    /// Modification time and date, which replace ones of every entry
+    pub(crate) reproducible: Option<(u16, u16)>,
+    pub(crate) time_zone: TimeZone,
+    pub(crate) extended_timestamp: bool,
+    pub(crate) container: Option<ContainerProfile>,
+    /// Archive comment, which is kept for appended archive
+    pub(crate) comment: Vec<u8>,
+    /// Preparation of appended archive, which is deferred until the first write
+    pub(crate) pending_truncate: Option<fn(&mut W, u64) -> io::Result<()>>,
 }
 
-impl<W: Write, P: AsRef<str>> AsMut<ZipWriter<W, P>> for ZipWriter<W, P> {
+impl<W: Write, P: ZipPath> AsMut<ZipWriter<W, P>> for ZipWriter<W, P> {
     fn as_mut(&mut self) -> &mut ZipWriter<W, P> {
         self
     }
 }
 
-impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
+impl<W: Write, P: ZipPath> ZipWriter<W, P> {
     pub(crate) fn write_entry_header(&mut self, header: &mut Header<P>) -> Result<()> {
+        self.check_cancelled()?;
+        if let Some((time, date)) = self.reproducible {
+            header.modification_time = time;
+            header.modification_date = date;
+        }
+        header.apply_path_policy(&self.path_policy)?;
+        self.apply_container(header)?;
+        self.apply_duplicate_policy(header)?;
+        let parent_dirs_path = self.parent_dirs_path(header);
+        header.apply_path_encoding(self.path_encoding)?;
+        if header.path_bytes().len() > u16::MAX as usize {
+            return Err(FormatLimit::PathTooLong(header.path_bytes().len()).into());
+        }
+        if MAX_ZIP64_EXTRA_LENGTH + header.extra_field.len() > u16::MAX as usize {
+            return Err(FormatLimit::ExtraFieldTooLong(
+                MAX_ZIP64_EXTRA_LENGTH + header.extra_field.len(),
+            )
+            .into());
+        }
+        if let Some(path) = parent_dirs_path {
+            self.write_parent_dirs(&path)?;
+        }
+        self.prepare_sink()?;
+        if let Some(paths) = &mut self.paths {
+            paths.insert(header.path_bytes().to_owned());
+        }
+
         header.offset = self.position;
 
         self.write.write_u32::<LittleEndian>(0x04034b50)?; // magic number
-        self.write.write_u16::<LittleEndian>(0x2D)?; // version
-        self.write.write_u16::<LittleEndian>(
-            0b0000_0000_0000_1000 | ((!header.path_str().is_ascii() as u16) << 11),
-        )?; // general purpose flag
+        self.write
+            .write_u16::<LittleEndian>(header.version_needed())?; // version
+        self.write.write_u16::<LittleEndian>(header.flags)?; // general purpose flag
         self.write
             .write_u16::<LittleEndian>(header.compression_id)?; // compression method
         self.write
@@ -57,58 +287,162 @@ impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
         self.write.write_u32::<LittleEndian>(0xFFFFFFFF)?; // uncompressed size
         self.write
             .write_u16::<LittleEndian>(header.path_bytes().len() as u16)?; // file name length
-        self.write.write_u16::<LittleEndian>(20)?; // extra field length
+        self.write
+            .write_u16::<LittleEndian>(20 + header.extra_field.len() as u16)?; // extra field length
         self.write.write_all(header.path_bytes())?; // path
 
         self.write.write_u16::<LittleEndian>(0x0001)?; // header id (ZIP64)
         self.write.write_u16::<LittleEndian>(16)?;
         self.write.write_u64::<LittleEndian>(0)?;
         self.write.write_u64::<LittleEndian>(0)?;
+        self.write.write_all(&header.extra_field)?;
 
-        self.position += 30 + header.path_bytes().len() as u64 + 20;
+        self.position +=
+            30 + header.path_bytes().len() as u64 + 20 + header.extra_field.len() as u64;
+        self.entry_bytes_out = 0;
+        self.next_progress = self.progress_interval;
+        self.notify(Event::EntryStarted {
+            path: header.path_bytes(),
+        });
 
         Ok(())
     }
 
-    pub(crate) fn write_entry_data_descriptor(&mut self, header: &Header<P>) -> Result<()> {
-        self.write.write_u32::<LittleEndian>(0x08074b50)?; // data descriptor signature
-        self.write.write_u32::<LittleEndian>(header.crc32)?;
+    pub(crate) fn prepare_sink(&mut self) -> Result<()> {
+        if let Some(truncate) = self.pending_truncate.take() {
+            truncate(&mut self.write, self.position)?;
+        }
 
-        if header.uncompressed_size < 0xFFFFFFFF && header.compressed_size < 0xFFFFFFFF {
-            self.write
-                .write_u32::<LittleEndian>(header.uncompressed_size as u32)?;
-            self.write
-                .write_u32::<LittleEndian>(header.compressed_size as u32)?;
+        Ok(())
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
 
-            self.position += 16;
+    pub(crate) fn notify(&mut self, event: Event<'_>) {
+        if let Some(observer) = &mut self.observer {
+            observer.event(event);
+        }
+    }
+
+    pub(crate) fn report_progress(&mut self, bytes_in: u64) {
+        if self.observer.is_some() && bytes_in >= self.next_progress {
+            self.next_progress = bytes_in + self.progress_interval;
+            let bytes_out = self.entry_bytes_out;
+            self.notify(Event::Progress {
+                bytes_in,
+                bytes_out,
+            });
+        }
+    }
+
+    pub(crate) fn notify_entry_end(&mut self, header: &Header<P>) {
+        let event = if header.aborted {
+            Event::EntryAborted {
+                path: header.path_bytes(),
+            }
         } else {
-            self.write
-                .write_u64::<LittleEndian>(header.uncompressed_size)?;
-            self.write
-                .write_u64::<LittleEndian>(header.compressed_size)?;
+            Event::EntryFinished {
+                path: header.path_bytes(),
+                data: EntryData {
+                    uncompressed_size: header.uncompressed_size,
+                    compressed_size: header.compressed_size,
+                },
+                crc32: header.crc32,
+            }
+        };
+        self.notify(event);
+    }
 
-            self.position += 24;
+    /// Rejects or renames entry with already written path. Paths are compared as they
+    /// are written into archive, i.e. encoded, and new path is added to index only
+    /// after all checks of entry pass
+    fn apply_duplicate_policy(&mut self, header: &mut Header<P>) -> Result<()> {
+        let paths = match &self.paths {
+            Some(paths) => paths,
+            None => return Ok(()),
+        };
+        let encoding = self.path_encoding;
+        if !paths.contains(&*encoding.encode(header.path_bytes())) {
+            return Ok(());
         }
 
+        match self.duplicate_policy {
+            DuplicatePolicy::Allow => Ok(()),
+            DuplicatePolicy::Error => Err(Error::DuplicatePath(header.path_lossy())),
+            DuplicatePolicy::Rename => {
+                let path = (1..)
+                    .map(|n| with_suffix(header.path_bytes(), n))
+                    .find(|path| !paths.contains(&*encoding.encode(path)))
+                    .unwrap();
+                header.path = HeaderPath::Owned(path);
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Path of aborted entry can be used again
+    pub(crate) fn forget_path(&mut self, header: &Header<P>) {
+        if let Some(paths) = &mut self.paths {
+            paths.remove(header.path_bytes());
+        }
+    }
+
+    pub(crate) fn write_entry_data_descriptor(&mut self, header: &Header<P>) -> Result<()> {
+        self.write.write_u32::<LittleEndian>(0x08074b50)?; // data descriptor signature
+        self.write.write_u32::<LittleEndian>(header.crc32)?;
+
+        // local header always has ZIP64 extended information, so sizes are always 64-bit
+        self.write
+            .write_u64::<LittleEndian>(header.compressed_size)?;
+        self.write
+            .write_u64::<LittleEndian>(header.uncompressed_size)?;
+
+        self.position += 24;
+
         Ok(())
     }
 
     pub(crate) fn write_central_directory(mut self) -> Result<W> {
+        self.start_container()?;
+        self.prepare_sink()?;
         let entries_count = self.entries.len() as u64;
         let mut central_directory_size = 0u64;
         let central_directory_offset = self.position;
-        for header in self.entries {
-            let overflow_fields = (header.uncompressed_size >= 0xFFFFFFFF
-                || header.compressed_size >= 0xFFFFFFFF) as u16
-                * 2
-                + (header.offset >= 0xFFFFFFFF) as u16;
+        let version_needed = self
+            .entries
+            .iter()
+            .map(Header::version_needed)
+            .fold(0x2D, max);
+        for header in std::mem::take(&mut self.entries) {
+            let sizes_overflow =
+                header.uncompressed_size >= 0xFFFFFFFF || header.compressed_size >= 0xFFFFFFFF;
+            let overflow_fields = sizes_overflow as u16 * 2 + (header.offset >= 0xFFFFFFFF) as u16;
+
+            // both sizes are moved to ZIP64 extra field, if any of them overflows
+            let (compressed_size, uncompressed_size) = if sizes_overflow {
+                (0xFFFFFFFF, 0xFFFFFFFF)
+            } else {
+                (
+                    header.compressed_size as u32,
+                    header.uncompressed_size as u32,
+                )
+            };
 
             self.write.write_u32::<LittleEndian>(0x02014b50)?; // signature
-            self.write.write_u16::<LittleEndian>(0x2D)?; // version made by
-            self.write.write_u16::<LittleEndian>(0x2D)?; // version to extract
             self.write.write_u16::<LittleEndian>(
-                0b0000_0000_0000_1000 | ((!header.path_str().is_ascii() as u16) << 11),
-            )?; // general purpose bit flag
+                header
+                    .version_made_by
+                    .unwrap_or_else(|| header.version_needed()),
+            )?; // version made by
+            self.write
+                .write_u16::<LittleEndian>(header.version_needed())?; // version to extract
+            self.write.write_u16::<LittleEndian>(header.flags)?; // general purpose bit flag
             self.write
                 .write_u16::<LittleEndian>(header.compression_id)?; // compression method
             self.write
@@ -116,22 +450,24 @@ impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
             self.write
                 .write_u16::<LittleEndian>(header.modification_date)?; // last mod file date
             self.write.write_u32::<LittleEndian>(header.crc32)?; // crc32
-            self.write
-                .write_u32::<LittleEndian>(min(header.compressed_size, 0xFFFFFFFF) as u32)?; // compressed size
-            self.write
-                .write_u32::<LittleEndian>(min(header.uncompressed_size, 0xFFFFFFFF) as u32)?; // uncompressed size
+            self.write.write_u32::<LittleEndian>(compressed_size)?; // compressed size
+            self.write.write_u32::<LittleEndian>(uncompressed_size)?; // uncompressed size
             self.write
                 .write_u16::<LittleEndian>(header.path_bytes().len() as u16)?; // file name length
+            let zip64_extra_length = if overflow_fields > 0 {
+                4 + 8 * overflow_fields
+            } else {
+                0
+            };
+            self.write
+                .write_u16::<LittleEndian>(zip64_extra_length + header.extra_field.len() as u16)?; // extra field length
             self.write
-                .write_u16::<LittleEndian>(if overflow_fields > 0 {
-                    4 + 8 * overflow_fields
-                } else {
-                    0
-                })?; // extra field length
-            self.write.write_u16::<LittleEndian>(0)?; // file comment length
+                .write_u16::<LittleEndian>(header.comment.len() as u16)?; // file comment length
             self.write.write_u16::<LittleEndian>(0)?; // disk number start
-            self.write.write_u16::<LittleEndian>(0)?; // internal file attributes
-            self.write.write_u32::<LittleEndian>(0)?; // external file attributes
+            self.write
+                .write_u16::<LittleEndian>(header.internal_attributes)?; // internal file attributes
+            self.write
+                .write_u32::<LittleEndian>(header.external_attributes)?; // external file attributes
             self.write
                 .write_u32::<LittleEndian>(min(header.offset, 0xFFFFFFFF) as u32)?; // relative offset of local header
             self.write.write_all(header.path_bytes())?; // file name
@@ -139,7 +475,7 @@ impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
                 self.write.write_u16::<LittleEndian>(0x0001)?; // header id (ZIP64)
                 self.write.write_u16::<LittleEndian>(8 * overflow_fields)?;
 
-                if header.uncompressed_size >= 0xFFFFFFFF || header.compressed_size >= 0xFFFFFFFF {
+                if sizes_overflow {
                     self.write
                         .write_u64::<LittleEndian>(header.uncompressed_size)?;
                     self.write
@@ -150,11 +486,14 @@ impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
                     self.write.write_u64::<LittleEndian>(header.offset)?;
                 }
             }
+            self.write.write_all(&header.extra_field)?;
+            self.write.write_all(&header.comment)?; // file comment
 
-            central_directory_size += 46 + header.path_bytes().len() as u64;
-            if overflow_fields > 0 {
-                central_directory_size += 4 + 8 * overflow_fields as u64;
-            }
+            central_directory_size += 46
+                + header.path_bytes().len() as u64
+                + zip64_extra_length as u64
+                + header.extra_field.len() as u64
+                + header.comment.len() as u64;
         }
 
         self.position += central_directory_size;
@@ -163,8 +502,8 @@ impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
         // zip64
         self.write.write_u32::<LittleEndian>(0x06064b50)?; // signature (ZIP64 end of central directory)
         self.write.write_u64::<LittleEndian>(44)?;
-        self.write.write_u16::<LittleEndian>(0x2D)?; // version made by
-        self.write.write_u16::<LittleEndian>(0x2D)?; // version to extract
+        self.write.write_u16::<LittleEndian>(version_needed)?; // version made by
+        self.write.write_u16::<LittleEndian>(version_needed)?; // version to extract
         self.write.write_u32::<LittleEndian>(0)?; // number of this disk
         self.write.write_u32::<LittleEndian>(0)?; // disk where central directory starts
         self.write.write_u64::<LittleEndian>(entries_count)?; // Number of central directory records on this disk
@@ -184,13 +523,24 @@ impl<W: Write, P: AsRef<str>> ZipWriter<W, P> {
         self.write.write_u32::<LittleEndian>(0x06054b50)?;
         self.write.write_u16::<LittleEndian>(0)?; // number of this disk
         self.write.write_u16::<LittleEndian>(0)?; // disk where central directory starts
-        self.write.write_u16::<LittleEndian>(entries_count as u16)?; // number of central directory records on this disk
-        self.write.write_u16::<LittleEndian>(entries_count as u16)?; // number of central directory records total
+
+        // overflowed count is taken from ZIP64 end of central directory
+        self.write
+            .write_u16::<LittleEndian>(min(entries_count, 0xFFFF) as u16)?; // number of central directory records on this disk
+        self.write
+            .write_u16::<LittleEndian>(min(entries_count, 0xFFFF) as u16)?; // number of central directory records total
         self.write
             .write_u32::<LittleEndian>(min(central_directory_size, 0xFFFFFFFF) as u32)?; // size of the central directory
         self.write
             .write_u32::<LittleEndian>(min(central_directory_offset, 0xFFFFFFFF) as u32)?; // offset of central directory
-        self.write.write_u16::<LittleEndian>(0)?; // zip comment length
+        self.write
+            .write_u16::<LittleEndian>(self.comment.len() as u16)?; // zip comment length
+        self.write.write_all(&self.comment)?; // zip comment
+
+        self.notify(Event::CentralDirectoryWritten {
+            entries: entries_count,
+            size: central_directory_size,
+        });
 
         Ok(self.write)
     }